@@ -0,0 +1,169 @@
+//! Suggests how long the next work session and break should be.
+//!
+//! The newest focus rating decides the direction: flow and good focus
+//! lengthen the next work session, distraction shortens it. Steps are halved
+//! for sessions under ten minutes and quartered from 25 minutes on, so the
+//! length settles instead of running away, and a streak of flow ratings adds
+//! up to 90% on top. The result stays within `adaptive.min_duration` and
+//! `adaptive.max_duration`.
+//!
+//! Ratings come from the journal, so the app, the CLI and scripts calling
+//! `mytt suggest` all get the same answer. The same goes for the flow streak
+//! and the daily flow goal in `FocusStats`.
+use crate::config::Config;
+use crate::error::Result;
+use crate::journal::Journal;
+use crate::Session;
+use chrono::{DateTime, Local, NaiveTime};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// How many of the latest ratings are looked at; enough for any streak that
+/// makes a difference.
+const HISTORY: u32 = 10;
+
+/// How well a work session went, as rated by the user once it is over.
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum FocusRating {
+    Distracted,
+    Ok,
+    Focused,
+    Flow,
+}
+
+impl FocusRating {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FocusRating::Distracted => "distracted",
+            FocusRating::Ok => "ok",
+            FocusRating::Focused => "focused",
+            FocusRating::Flow => "flow",
+        }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self {
+            FocusRating::Flow => "Extended due to flow state",
+            FocusRating::Focused => "Increased due to good focus",
+            FocusRating::Ok => "Maintained current duration",
+            FocusRating::Distracted => "Reduced due to distraction",
+        }
+    }
+}
+
+impl FromStr for FocusRating {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "distracted" => Ok(FocusRating::Distracted),
+            "ok" => Ok(FocusRating::Ok),
+            "focused" => Ok(FocusRating::Focused),
+            "flow" => Ok(FocusRating::Flow),
+            _ => Err(format!("unknown focus rating `{}`", s)),
+        }
+    }
+}
+
+/// The suggested lengths of the next work session and the break before it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub work_seconds: u64,
+    pub break_seconds: u64,
+    pub reason: String,
+}
+
+/// Where the user stands with flow, as of the latest rating.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct FocusStats {
+    /// Latest rated work sessions in a row that were rated `flow`.
+    pub streak: u32,
+    /// Work sessions rated `flow` since midnight.
+    pub flow_today: u32,
+    /// `daily_flow_goal` from the configuration; 0 when there is none.
+    pub daily_goal: u32,
+}
+
+impl FocusStats {
+    pub fn goal_met(&self) -> bool {
+        self.daily_goal > 0 && self.flow_today >= self.daily_goal
+    }
+}
+
+/// The flow streak and progress towards the daily goal on the day of `now`.
+pub fn focus_stats(config: &Config, journal: &Journal, now: DateTime<Local>) -> Result<FocusStats> {
+    let midnight = now
+        .date_naive()
+        .and_time(NaiveTime::MIN)
+        .and_local_timezone(Local)
+        .earliest()
+        .unwrap_or(now);
+    Ok(FocusStats {
+        streak: journal.flow_streak()?,
+        flow_today: journal.flow_sessions_since(midnight)?,
+        daily_goal: config.daily_flow_goal(),
+    })
+}
+
+/// Suggests what follows the last work session of `session`, from the
+/// ratings in `journal`. `rating` is one given for that session that is not
+/// in the journal yet.
+pub fn suggest_next(
+    config: &Config,
+    session: &Session,
+    journal: &Journal,
+    rating: Option<FocusRating>,
+) -> Result<Suggestion> {
+    let mut ratings: Vec<_> = rating.into_iter().collect();
+    ratings.extend(journal.recent_ratings(HISTORY)?);
+    let current = session.last_work.unwrap_or(config.work_duration() * 60);
+    Ok(suggest(config, current, &ratings))
+}
+
+/// Suggests what follows a work session planned for `current` seconds,
+/// given `ratings` with the newest first.
+pub fn suggest(config: &Config, current: u64, ratings: &[FocusRating]) -> Suggestion {
+    let settings = config.adaptive.clone().unwrap_or_default();
+    let (min, max) = (settings.min_duration() * 60, settings.max_duration() * 60);
+    let Some(&rating) = ratings.first() else {
+        return Suggestion {
+            work_seconds: current.clamp(min, max),
+            break_seconds: config.short_break_duration() * 60,
+            reason: "No focus ratings yet".to_string(),
+        };
+    };
+    let streak = ratings
+        .iter()
+        .take_while(|r| **r == FocusRating::Flow)
+        .count() as i64;
+
+    let mut step = settings.increment(rating) * 60;
+    if current < 10 * 60 {
+        step = step.div_euclid(2);
+    } else if current >= 25 * 60 {
+        step = step.div_euclid(4);
+    }
+    if rating == FocusRating::Flow {
+        // 30% more for each flow rating in a row, up to three.
+        step = (step * (10 + 3 * streak.min(3))).div_euclid(10);
+    }
+    let reason = if streak > 1 {
+        format!("Flow streak ({}x) bonus applied", streak)
+    } else {
+        rating.reason().to_string()
+    };
+
+    // A long run of flow still earns a shorter break than a single one.
+    let break_rating = match rating {
+        FocusRating::Flow if streak > 3 => FocusRating::Focused,
+        rating => rating,
+    };
+    Suggestion {
+        work_seconds: (current as i64 + step).clamp(min as i64, max as i64) as u64,
+        break_seconds: settings.break_minutes(break_rating) * 60,
+        reason,
+    }
+}
@@ -0,0 +1,447 @@
+use crate::adaptive::{self, FocusRating, FocusStats, Suggestion};
+use crate::config::{self, get_scripts, RUNTIME_DIR, SCRIPTS_DIR};
+use crate::error::Result;
+use crate::hooks::{self, HookContext, HookLog, HookResponse};
+use crate::idle::IdleAction;
+#[cfg(unix)]
+use crate::ipc::{self, Client, Request, Response};
+use crate::journal::{Journal, NewJournalEntry};
+use crate::{
+    format_millis, frontmost_application_name, frontmost_window, journal_entry, CommandExecutor,
+    Phase, Sequence, Session, SessionEvent, SessionType, Status, Stopwatch, StopwatchSnapshot,
+    TimerState, Transition,
+};
+use anyhow::Context;
+use chrono::Local;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// A simple and efficient time tracking tool for your daily workflow.
+///
+/// Without a subcommand the desktop app is started.
+#[derive(Parser)]
+#[command(name = "mytt", version)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Start a new session
+    Start,
+    /// Stop the current session without logging it
+    Stop,
+    /// Pause the current session
+    Pause,
+    /// Resume a paused session
+    Resume,
+    /// Log the current session and stop it
+    Log,
+    /// Print the current session type, elapsed and remaining time
+    Status,
+    /// Stream state changes from the running app as JSON lines
+    Watch,
+    /// List hook events, their scripts and how they last ran
+    Hooks,
+    /// Settle the time spent away from a session that was paused as idle
+    Idle {
+        action: IdleAction,
+        /// Session type to log the time as with `reassign`
+        #[arg(long = "as", value_name = "TYPE")]
+        session_type: Option<SessionType>,
+    },
+    /// Split the current session without stopping it
+    Lap {
+        /// What the time since the previous lap went on
+        label: Option<String>,
+    },
+    /// List the session sequences, or switch to another one
+    Sequence {
+        /// Name of the sequence to switch to
+        name: Option<String>,
+    },
+    /// Suggest the next work and break lengths from the focus ratings
+    Suggest {
+        /// A rating for the last session that has not been logged
+        #[arg(long)]
+        rating: Option<FocusRating>,
+        /// Print the suggestion as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Rate your focus during the work session that just finished
+    Rate {
+        rating: FocusRating,
+        /// A note to keep with the session
+        #[arg(long)]
+        note: Option<String>,
+    },
+}
+
+/// The subcommand the arguments ask for, or `None` to start the app.
+///
+/// Launchers add arguments of their own, like the `-psn_…` macOS Finder
+/// passes, and those start the app as well. Help, version and mistakes in
+/// the arguments of a known subcommand are reported and exit.
+pub fn parse() -> Option<Command> {
+    match Cli::try_parse() {
+        Ok(cli) => cli.command,
+        Err(e) => {
+            let subcommand = std::env::args()
+                .nth(1)
+                .is_some_and(|arg| Cli::command().find_subcommand(&arg).is_some());
+            let shown = matches!(
+                e.kind(),
+                ErrorKind::DisplayHelp
+                    | ErrorKind::DisplayVersion
+                    | ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand
+            );
+            if subcommand || shown {
+                e.exit();
+            }
+            None
+        }
+    }
+}
+
+/// Timer state kept between CLI invocations.
+#[derive(Serialize, Deserialize)]
+struct HeadlessState {
+    stopwatch: StopwatchSnapshot,
+    session: Session,
+}
+
+impl HeadlessState {
+    fn path() -> PathBuf {
+        RUNTIME_DIR.join("headless.json")
+    }
+
+    fn load() -> anyhow::Result<(Stopwatch, Session)> {
+        let path = Self::path();
+        if !path.exists() {
+            return Ok((Stopwatch::new(), Session::with_config(&config::current())));
+        }
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let state: HeadlessState = serde_json::from_str(&contents)
+            .with_context(|| format!("failed to parse {}", path.display()))?;
+        // Pick up edits to config.yaml made since the last command.
+        let mut session = state.session;
+        session.apply_config(&config::current());
+        let stopwatch = Stopwatch::restore(&state.stopwatch);
+        // State saved before sessions had a phase.
+        if session.phase() == Phase::Idle && stopwatch.state() != TimerState::Stopped {
+            session.apply(Transition::Start)?;
+            if stopwatch.state() == TimerState::Paused {
+                session.apply(Transition::Pause)?;
+            }
+        }
+        Ok((stopwatch, session))
+    }
+
+    fn save(stopwatch: &Stopwatch, session: Session) -> anyhow::Result<()> {
+        let state = HeadlessState {
+            stopwatch: stopwatch.snapshot(),
+            session,
+        };
+        std::fs::create_dir_all(RUNTIME_DIR.as_path())?;
+        std::fs::write(Self::path(), serde_json::to_string_pretty(&state)?)?;
+        Ok(())
+    }
+}
+
+/// Runs the scripts for `event` and merges what they hand back.
+fn run_hook(
+    executor: &CommandExecutor,
+    event: &str,
+    stopwatch: &Stopwatch,
+    session: &Session,
+) -> Result<HookResponse> {
+    queue_hook(executor, HookContext::new(event, stopwatch, session))
+}
+
+fn queue_hook(executor: &CommandExecutor, context: HookContext) -> Result<HookResponse> {
+    let scripts = get_scripts(&context.event, &context.session_type);
+    if scripts.is_empty() {
+        return Ok(HookResponse::default());
+    }
+    let context = context.with_frontmost(frontmost_window());
+    let settings = config::current().hook(&context.event);
+    let mut pending = Vec::new();
+    for path in scripts {
+        pending.push(executor.execute(path, context.clone(), settings.clone())?);
+    }
+    Ok(hooks::responses(pending))
+}
+
+/// Moves the session along `transition`, sets the stopwatch to match and
+/// runs the `on_transition` scripts.
+fn step(
+    executor: &CommandExecutor,
+    transition: Transition,
+    stopwatch: &mut Stopwatch,
+    session: &mut Session,
+) -> Result<SessionEvent> {
+    let elapsed = Duration::from_millis(stopwatch.elapsed() as u64);
+    let event = session.apply_timed(transition, elapsed)?;
+    stopwatch.follow(&event.to);
+    let mut context = HookContext::new("on_transition", stopwatch, session);
+    context.transition = Some(transition);
+    queue_hook(executor, context)?;
+    Ok(event)
+}
+
+/// Logs the session being timed, with the focus rating and note if given.
+fn log(
+    executor: &CommandExecutor,
+    stopwatch: &mut Stopwatch,
+    session: &mut Session,
+    rating: Option<FocusRating>,
+    note: Option<String>,
+) -> anyhow::Result<()> {
+    session.check(Transition::Log)?;
+    let focus_rating = rating.map(|rating| rating.as_str().to_string());
+    let context = |event| HookContext {
+        focus_rating: focus_rating.clone(),
+        note: note.clone(),
+        ..HookContext::new(event, stopwatch, session)
+    };
+    let mut response = queue_hook(executor, context("on_log"))?;
+    response.merge(queue_hook(executor, context("on_stop"))?);
+    if let Some(entry) = journal_entry(stopwatch, session, frontmost_application_name()) {
+        Journal::open_default()?.insert(&NewJournalEntry {
+            focus_rating,
+            note: note.or(response.note),
+            tags: response.tags,
+            ..entry
+        })?;
+    }
+    step(executor, Transition::Log, stopwatch, session)?;
+    if let Some(seconds) = response.next_duration {
+        session.set_target_duration(seconds);
+    }
+    Ok(())
+}
+
+fn print_status(status: &Status) {
+    println!("session:   {}", status.session_type.as_str());
+    println!("state:     {}", status.state.as_str());
+    println!("elapsed:   {}", format_millis(status.elapsed_ms as u128));
+    println!("remaining: {}", format_millis(status.remaining_ms as u128));
+}
+
+fn print_hooks() -> anyhow::Result<()> {
+    let hooks = hooks::inventory(&SCRIPTS_DIR, &config::current(), &HookLog::default())?;
+    for hook in hooks {
+        let required = if hook.required { " (required)" } else { "" };
+        println!("{}{}", hook.event, required);
+        if hook.scripts.is_empty() {
+            println!("  no scripts");
+        }
+        for script in hook.scripts {
+            let state = match (script.enabled, script.executable) {
+                (false, _) => "disabled",
+                (true, false) => "not executable",
+                (true, true) => "enabled",
+            };
+            let last_run = match &script.last_run {
+                Some(run) if run.success => format!("ok {}", run.started_at.format("%F %T")),
+                Some(run) => run.failure().unwrap_or_default(),
+                None => "never run".to_string(),
+            };
+            println!("  {}  [{}]  {}", script.path.display(), state, last_run);
+        }
+    }
+    Ok(())
+}
+
+/// Lists the sequences with the selected one marked, or selects `name`. The
+/// choice is saved to `config.yaml`, where the running app picks it up.
+fn sequence(name: Option<&str>) -> anyhow::Result<()> {
+    if let Some(name) = name {
+        config::select_sequence(name)?;
+        return Ok(());
+    }
+    let config = config::current();
+    for sequence in Sequence::all(&config) {
+        let marker = if sequence.name == config.sequence() {
+            "*"
+        } else {
+            " "
+        };
+        let segments: Vec<String> = sequence
+            .segments
+            .iter()
+            .map(|s| format!("{} {}m", s.session_type.as_str(), s.minutes))
+            .collect();
+        println!("{} {}  {}", marker, sequence.name, segments.join(", "));
+    }
+    Ok(())
+}
+
+fn print_focus_stats(stats: &FocusStats) {
+    println!("flow streak: {}", stats.streak);
+    match stats.daily_goal {
+        0 => println!("flow today:  {}", stats.flow_today),
+        goal => println!("flow today:  {}/{}", stats.flow_today, goal),
+    }
+}
+
+fn print_suggestion(suggestion: &Suggestion, json: bool) -> anyhow::Result<()> {
+    if json {
+        println!("{}", serde_json::to_string(&suggestion)?);
+    } else {
+        println!(
+            "work:   {}",
+            format_millis(suggestion.work_seconds as u128 * 1000)
+        );
+        println!(
+            "break:  {}",
+            format_millis(suggestion.break_seconds as u128 * 1000)
+        );
+        println!("reason: {}", suggestion.reason);
+    }
+    Ok(())
+}
+
+/// Runs a subcommand against the running app if there is one, and against
+/// the timer state persisted in `RUNTIME_DIR` otherwise.
+pub fn run(command: Command) -> anyhow::Result<()> {
+    if command == Command::Hooks {
+        return print_hooks();
+    }
+    if let Command::Sequence { name } = &command {
+        return sequence(name.as_deref());
+    }
+    #[cfg(unix)]
+    if let Some(client) = Client::connect(&ipc::socket_path())? {
+        return run_remote(client, command);
+    }
+    run_headless(command)
+}
+
+#[cfg(unix)]
+fn run_remote(mut client: Client, command: Command) -> anyhow::Result<()> {
+    let request = match command {
+        Command::Start => Request::Start,
+        Command::Stop => Request::Stop,
+        Command::Pause => Request::Pause,
+        Command::Resume => Request::Resume,
+        Command::Log => Request::Log,
+        Command::Status => Request::Status,
+        Command::Watch => Request::Subscribe,
+        Command::Hooks => unreachable!("hooks are listed without the app"),
+        Command::Sequence { .. } => unreachable!("sequences are chosen in config.yaml"),
+        Command::Suggest { rating, .. } => Request::Suggest { rating },
+        Command::Rate { rating, ref note } => Request::Rate {
+            rating,
+            note: note.clone(),
+        },
+        Command::Idle {
+            action,
+            ref session_type,
+        } => Request::ResolveIdle {
+            action,
+            session_type: session_type.clone(),
+        },
+        Command::Lap { ref label } => Request::Lap {
+            label: label.clone(),
+        },
+    };
+    match client.send(request)? {
+        Response::Error(message) => anyhow::bail!(message),
+        Response::Status(status) if command == Command::Status => print_status(&status),
+        Response::Suggestion(suggestion) => {
+            let json = matches!(command, Command::Suggest { json: true, .. });
+            print_suggestion(&suggestion, json)?
+        }
+        Response::FocusStats(stats) => print_focus_stats(&stats),
+        _ => {}
+    }
+    if command == Command::Watch {
+        while let Some(message) = client.recv()? {
+            if let Response::StateChanged(status) = message {
+                println!("{}", serde_json::to_string(&status)?);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Fires the same `on_*` hooks as the desktop app, but against the timer
+/// state persisted in `RUNTIME_DIR`.
+fn run_headless(command: Command) -> anyhow::Result<()> {
+    let (mut stopwatch, mut session) = HeadlessState::load()?;
+    let executor = CommandExecutor::new();
+
+    match command {
+        Command::Start => {
+            session.check(Transition::Start)?;
+            run_hook(&executor, "on_start", &stopwatch, &session)?;
+            step(&executor, Transition::Start, &mut stopwatch, &mut session)?;
+        }
+        Command::Stop => {
+            session.check(Transition::Stop)?;
+            let response = run_hook(&executor, "on_stop", &stopwatch, &session)?;
+            step(&executor, Transition::Stop, &mut stopwatch, &mut session)?;
+            if let Some(seconds) = response.next_duration {
+                session.set_target_duration(seconds);
+            }
+        }
+        Command::Pause => {
+            session.check(Transition::Pause)?;
+            run_hook(&executor, "on_pause", &stopwatch, &session)?;
+            step(&executor, Transition::Pause, &mut stopwatch, &mut session)?;
+        }
+        Command::Resume => {
+            session.check(Transition::Resume)?;
+            run_hook(&executor, "on_resume", &stopwatch, &session)?;
+            step(&executor, Transition::Resume, &mut stopwatch, &mut session)?;
+        }
+        Command::Log => log(&executor, &mut stopwatch, &mut session, None, None)?,
+        Command::Status => print_status(&Status::new(&stopwatch, &session)),
+        Command::Watch => anyhow::bail!("watch needs the mytt app to be running"),
+        Command::Hooks => unreachable!("hooks are listed without the app"),
+        Command::Sequence { .. } => unreachable!("sequences are chosen in config.yaml"),
+        Command::Suggest { rating, json } => {
+            let journal = Journal::open_default()?;
+            let suggestion =
+                adaptive::suggest_next(&config::current(), &session, &journal, rating)?;
+            print_suggestion(&suggestion, json)?;
+        }
+        Command::Rate { rating, note } => {
+            let awaiting = session.phase() == Phase::AwaitingRating;
+            if awaiting {
+                log(
+                    &executor,
+                    &mut stopwatch,
+                    &mut session,
+                    Some(rating),
+                    note.clone(),
+                )?;
+            }
+            let journal = Journal::open_default()?;
+            if !awaiting && journal.rate_latest(rating, note.as_deref())?.is_none() {
+                anyhow::bail!("there is no finished work session to rate");
+            }
+            let stats = adaptive::focus_stats(&config::current(), &journal, Local::now())?;
+            let context = HookContext::new("on_rate", &stopwatch, &session);
+            queue_hook(&executor, context.with_rating(rating, note, stats.clone()))?;
+            print_focus_stats(&stats);
+        }
+        Command::Idle { .. } => anyhow::bail!("idle time is only tracked by the running mytt app"),
+        Command::Lap { label } => {
+            stopwatch
+                .lap(label)
+                .context("there is no session to split")?;
+        }
+    }
+
+    HeadlessState::save(&stopwatch, session)?;
+    executor.wait();
+    Ok(())
+}
@@ -0,0 +1,79 @@
+//! Where the timer gets the time from.
+//!
+//! [`Stopwatch`](crate::Stopwatch) asks a [`Clock`] instead of calling
+//! `Instant::now()` directly, and everything that schedules around it (the
+//! countdown, session completion, journal timestamps) goes through the
+//! stopwatch. Tests swap in a `FakeClock` to check pause/resume arithmetic,
+//! long-break cadence and completion without sleeping.
+use chrono::{DateTime, Local};
+use std::time::Instant;
+
+pub trait Clock: Send + Sync {
+    /// Monotonic time, for measuring how long the timer ran.
+    fn now(&self) -> Instant;
+    /// Wall-clock time, for timestamps.
+    fn wall(&self) -> DateTime<Local>;
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn wall(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+#[cfg(test)]
+pub use fake::FakeClock;
+
+#[cfg(test)]
+mod fake {
+    use super::Clock;
+    use chrono::{DateTime, Local};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    /// A clock that only moves when told to. Clones share the same time.
+    #[derive(Debug, Clone)]
+    pub struct FakeClock {
+        time: Arc<Mutex<(Instant, DateTime<Local>)>>,
+    }
+
+    impl Default for FakeClock {
+        fn default() -> Self {
+            Self {
+                time: Arc::new(Mutex::new((Instant::now(), Local::now()))),
+            }
+        }
+    }
+
+    impl FakeClock {
+        /// Moves both clocks on by `by`.
+        pub fn advance(&self, by: Duration) {
+            let mut time = self.time.lock().unwrap();
+            time.0 += by;
+            time.1 += chrono::Duration::from_std(by).unwrap();
+        }
+
+        /// Moves only the wall clock on by `by`, which is how a system
+        /// suspend looks on Linux and macOS.
+        pub fn suspend(&self, by: Duration) {
+            self.time.lock().unwrap().1 += chrono::Duration::from_std(by).unwrap();
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> Instant {
+            self.time.lock().unwrap().0
+        }
+
+        fn wall(&self) -> DateTime<Local> {
+            self.time.lock().unwrap().1
+        }
+    }
+}
@@ -1,13 +1,20 @@
+use crate::adaptive::FocusRating;
+use crate::error::{MyttError, Result};
 use crate::notify_on_error;
+use crate::scripting;
+use crate::session::{Sequence, SessionType};
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
-use serde_yaml;
+use std::collections::BTreeMap;
 use std::env;
+use std::fmt;
 // use std::fs::{read, read_to_string};
 use std::path::PathBuf;
+use std::sync::RwLock;
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
 pub enum AnnoyingLevel {
+    #[default]
     None,
     #[serde(alias = "low")]
     Low,
@@ -17,14 +24,21 @@ pub enum AnnoyingLevel {
     High,
 }
 
-impl Default for AnnoyingLevel {
-    fn default() -> Self {
-        AnnoyingLevel::None
-    }
+/// What happens to a running session while the machine is suspended.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SuspendPolicy {
+    /// Count the time asleep as part of the session.
+    Count,
+    /// Leave the time asleep out of the session and keep it running.
+    #[default]
+    Exclude,
+    /// Pause the session as of when the machine went to sleep.
+    Pause,
 }
 
 // We will use yaml for configuration
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
 pub struct Config {
     pub short_break_duration: Option<u64>,
     pub long_break_duration: Option<u64>,
@@ -33,9 +47,154 @@ pub struct Config {
     pub work_sound: Option<String>,
     pub break_sound: Option<String>,
     pub annoying_level: Option<AnnoyingLevel>,
+    #[serde(alias = "sessions_before_long_break")]
     pub sessions_long_break: Option<u64>,
+    pub frontmost_app: Option<String>,
+    /// Seconds a hook may run before it is killed, unless overridden below.
+    pub hook_timeout: Option<u64>,
+    /// Per-event hook settings, keyed by event name such as `on_log`.
+    pub hooks: Option<BTreeMap<String, HookSettings>>,
+    /// Events that should have a script; a warning is shown when they do not.
+    pub required_hooks: Option<Vec<String>>,
+    /// Minutes without keyboard or mouse input before a running work session
+    /// is paused; 0 turns idle detection off.
+    pub idle_threshold: Option<u64>,
+    pub suspend_policy: Option<SuspendPolicy>,
+    /// The sequence of sessions to run, by name; `classic` when unset.
+    pub sequence: Option<String>,
+    /// Cycles of sessions other than the classic Pomodoro one, by name.
+    pub sequences: Option<BTreeMap<String, Vec<Segment>>>,
+    /// How long breaks are in the `flowtime` sequence.
+    pub flowtime: Option<Flowtime>,
+    /// How suggested session lengths follow the focus ratings.
+    pub adaptive: Option<Adaptive>,
+    /// Work sessions rated `flow` to aim for each day; 0 sets no goal.
+    pub daily_flow_goal: Option<u32>,
+}
+
+/// One step of a sequence.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Segment {
+    /// `work`, `shortBreak`, `longBreak` or a name of its own.
+    #[serde(rename = "type")]
+    pub session_type: SessionType,
+    pub minutes: u64,
+    /// Whether the segment is a break. Only needed for segments with a type
+    /// of their own, which are not breaks unless this says so.
+    #[serde(rename = "break", default, skip_serializing_if = "Option::is_none")]
+    pub is_break: Option<bool>,
+    /// Sound of the notification when the segment is over.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sound: Option<String>,
+}
+
+impl Segment {
+    pub fn is_break(&self) -> bool {
+        self.is_break
+            .unwrap_or_else(|| self.session_type.is_break())
+    }
+}
+
+/// Break lengths for flowtime, where work runs for as long as it lasts and
+/// the break after it is worked out from its length.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct Flowtime {
+    /// Minutes of work that earn one minute of break.
+    pub ratio: Option<u64>,
+    /// Fixed breaks by how long the work went on, which take the place of
+    /// `ratio`. The first bracket the work fits in applies; the last one
+    /// also covers anything longer.
+    pub brackets: Option<Vec<Bracket>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Bracket {
+    /// Minutes of work this bracket goes up to; open-ended when unset.
+    pub up_to: Option<u64>,
+    /// Length of the break in minutes.
+    pub minutes: u64,
+}
+
+const DEFAULT_FLOWTIME_RATIO: u64 = 5;
+
+impl Flowtime {
+    pub fn ratio(&self) -> u64 {
+        self.ratio.unwrap_or(DEFAULT_FLOWTIME_RATIO).max(1)
+    }
+
+    /// Length in seconds of the break earned by `worked`.
+    pub fn break_for(&self, worked: std::time::Duration) -> u64 {
+        let minutes = worked.as_secs() / 60;
+        let brackets = self.brackets.as_deref().unwrap_or_default();
+        let bracket = brackets
+            .iter()
+            .find(|bracket| minutes <= bracket.up_to.unwrap_or(u64::MAX))
+            .or(brackets.last());
+        match bracket {
+            Some(bracket) => bracket.minutes * 60,
+            None => worked.as_secs() / self.ratio(),
+        }
+    }
+}
+
+/// Limits and steps for the session lengths `mytt suggest` comes up with,
+/// all in minutes.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct Adaptive {
+    pub min_duration: Option<u64>,
+    pub max_duration: Option<u64>,
+    /// Added to the next work session after each rating; negative to
+    /// shorten it.
+    pub increments: Option<BTreeMap<FocusRating, i64>>,
+    /// Length of the break after each rating.
+    pub breaks: Option<BTreeMap<FocusRating, u64>>,
+}
+
+impl Adaptive {
+    pub fn min_duration(&self) -> u64 {
+        self.min_duration.unwrap_or(2)
+    }
+
+    pub fn max_duration(&self) -> u64 {
+        self.max_duration.unwrap_or(50).max(self.min_duration())
+    }
+
+    pub fn increment(&self, rating: FocusRating) -> i64 {
+        let configured = self.increments.as_ref().and_then(|i| i.get(&rating));
+        configured.copied().unwrap_or(match rating {
+            FocusRating::Flow => 10,
+            FocusRating::Focused => 5,
+            FocusRating::Ok => 2,
+            FocusRating::Distracted => -3,
+        })
+    }
+
+    pub fn break_minutes(&self, rating: FocusRating) -> u64 {
+        let configured = self.breaks.as_ref().and_then(|b| b.get(&rating));
+        configured.copied().unwrap_or(match rating {
+            FocusRating::Flow => 3,
+            FocusRating::Focused => 2,
+            FocusRating::Ok => 3,
+            FocusRating::Distracted => 5,
+        })
+    }
 }
 
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct HookSettings {
+    pub timeout: Option<u64>,
+    /// Run alongside other hooks instead of waiting in line behind them.
+    pub detached: Option<bool>,
+    /// File names of scripts for this event that should not run.
+    pub disabled: Option<Vec<String>>,
+}
+
+const DEFAULT_HOOK_TIMEOUT: u64 = 60;
+const DEFAULT_IDLE_THRESHOLD: u64 = 5;
+
+// Alternative spellings accepted for a key, canonical name first.
+const KEY_ALIASES: &[&[&str]] = &[&["sessions_long_break", "sessions_before_long_break"]];
+
 impl Config {
     pub fn short_break_duration(&self) -> u64 {
         self.short_break_duration.unwrap_or(5)
@@ -73,31 +232,708 @@ impl Config {
         self.annoying_level() == AnnoyingLevel::High
     }
 
-    pub fn load() -> Self {
-        let config_file = CONFIG_DIRS[0].join("config.yaml");
+    pub fn daily_flow_goal(&self) -> u32 {
+        self.daily_flow_goal.unwrap_or(3)
+    }
+
+    pub fn sequence(&self) -> &str {
+        self.sequence.as_deref().unwrap_or(Sequence::CLASSIC)
+    }
+
+    pub fn suspend_policy(&self) -> SuspendPolicy {
+        self.suspend_policy.unwrap_or_default()
+    }
+
+    /// `None` when idle detection is turned off.
+    pub fn idle_threshold(&self) -> Option<std::time::Duration> {
+        match self.idle_threshold.unwrap_or(DEFAULT_IDLE_THRESHOLD) {
+            0 => None,
+            minutes => Some(std::time::Duration::from_secs(minutes * 60)),
+        }
+    }
+
+    pub fn is_required_hook(&self, event: &str) -> bool {
+        self.required_hooks
+            .iter()
+            .flatten()
+            .any(|required| required == event)
+    }
+
+    /// Settings for the hook of `event`, with the defaults filled in.
+    pub fn hook(&self, event: &str) -> HookSettings {
+        let settings = self
+            .hooks
+            .as_ref()
+            .and_then(|hooks| hooks.get(event))
+            .cloned()
+            .unwrap_or_default();
+        HookSettings {
+            timeout: settings
+                .timeout
+                .or(self.hook_timeout)
+                .or(Some(DEFAULT_HOOK_TIMEOUT)),
+            detached: Some(settings.detached.unwrap_or(false)),
+            disabled: settings.disabled,
+        }
+    }
+
+    /// Every kind of session the sequences can run, built-in kinds first.
+    pub fn session_types(&self) -> Vec<SessionType> {
+        let mut types = vec![
+            SessionType::Work,
+            SessionType::ShortBreak,
+            SessionType::LongBreak,
+        ];
+        for sequence in Sequence::all(self) {
+            for segment in sequence.segments {
+                if !types.contains(&segment.session_type) {
+                    types.push(segment.session_type);
+                }
+            }
+        }
+        types
+    }
+
+    pub fn file() -> PathBuf {
+        CONFIG_DIRS[0].join("config.yaml")
+    }
+
+    /// Loads and validates `config.yaml`. Warnings (e.g. unknown keys) are
+    /// reported as a notification; any error rejects the whole file.
+    pub fn load() -> Result<Self> {
+        let config_file = Self::file();
         if !config_file.exists() {
-            let config = Config::default();
-            config.save();
-            return config;
+            std::fs::create_dir_all(&CONFIG_DIRS[0])?;
+            std::fs::write(config_file, CONFIG_TEMPLATE)?;
+            return Ok(Config::default());
+        }
+
+        let config_str = std::fs::read_to_string(config_file)?;
+        let check = ConfigCheck::new(&config_str);
+        let warnings: Vec<String> = check.warnings().map(|d| d.to_string()).collect();
+        if !warnings.is_empty() {
+            crate::notify!(warnings.join("\n"));
+        }
+        check.into_result()
+    }
+
+    /// Like `load`, but reports errors as a notification and falls back to
+    /// the defaults instead of failing.
+    pub fn load_or_default() -> Self {
+        Self::load().unwrap_or_else(|e| {
+            notify_on_error!(e);
+            Config::default()
+        })
+    }
+
+    fn validate(&self, text: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let mut require_positive = |key: &str, value: Option<u64>, what: &str| {
+            if value == Some(0) {
+                diagnostics.push(Diagnostic::at_key(
+                    Severity::Error,
+                    format!("{} must be {}", key, what),
+                    text,
+                    &[key],
+                ));
+            }
+        };
+        require_positive(
+            "short_break_duration",
+            self.short_break_duration,
+            "greater than 0",
+        );
+        require_positive(
+            "long_break_duration",
+            self.long_break_duration,
+            "greater than 0",
+        );
+        require_positive("work_duration", self.work_duration, "greater than 0");
+        require_positive(
+            "sessions_long_break",
+            self.sessions_long_break,
+            "at least 1",
+        );
+        require_positive("hook_timeout", self.hook_timeout, "greater than 0");
+        for event in self.required_hooks.iter().flatten() {
+            if !crate::hooks::EVENTS.contains(&event.as_str()) {
+                diagnostics.push(Diagnostic::at_key(
+                    Severity::Warning,
+                    format!("required_hooks: `{}` is not a hook event", event),
+                    text,
+                    &["required_hooks"],
+                ));
+            }
+        }
+        let sequences = self.sequences.clone().unwrap_or_default();
+        if let Some(name) = &self.sequence {
+            if !Sequence::is_built_in(name) && !sequences.contains_key(name) {
+                diagnostics.push(Diagnostic::at_key(
+                    Severity::Error,
+                    format!("sequence: `{}` is not one of the sequences", name),
+                    text,
+                    &["sequence"],
+                ));
+            }
+        }
+        for (name, segments) in &sequences {
+            let mut error = |message: String| {
+                diagnostics.push(Diagnostic::at_key(
+                    Severity::Error,
+                    message,
+                    text,
+                    &["sequences", name],
+                ))
+            };
+            if Sequence::is_built_in(name) {
+                error(format!("sequences.{} is built in", name));
+            }
+            if segments.is_empty() {
+                error(format!("sequences.{} must have at least one segment", name));
+            }
+            if segments.iter().any(|segment| segment.minutes == 0) {
+                error(format!(
+                    "sequences.{}: minutes must be greater than 0",
+                    name
+                ));
+            }
+            // The type names a directory of hook scripts.
+            for segment in segments {
+                let session_type = segment.session_type.as_str();
+                if session_type.contains(['/', '\\']) || session_type.contains("..") {
+                    error(format!(
+                        "sequences.{}: type `{}` must be a plain name",
+                        name, session_type
+                    ));
+                }
+            }
+        }
+        if let Some(adaptive) = &self.adaptive {
+            if adaptive.min_duration == Some(0) {
+                diagnostics.push(Diagnostic::at_key(
+                    Severity::Error,
+                    "adaptive.min_duration must be greater than 0".to_string(),
+                    text,
+                    &["adaptive", "min_duration"],
+                ));
+            }
+            if let (Some(min), Some(max)) = (adaptive.min_duration, adaptive.max_duration) {
+                if max < min {
+                    diagnostics.push(Diagnostic::at_key(
+                        Severity::Error,
+                        "adaptive.max_duration must not be less than min_duration".to_string(),
+                        text,
+                        &["adaptive", "max_duration"],
+                    ));
+                }
+            }
+        }
+        if self.flowtime.as_ref().and_then(|f| f.ratio) == Some(0) {
+            diagnostics.push(Diagnostic::at_key(
+                Severity::Error,
+                "flowtime.ratio must be greater than 0".to_string(),
+                text,
+                &["flowtime", "ratio"],
+            ));
+        }
+        for (event, settings) in self.hooks.iter().flatten() {
+            if settings.timeout == Some(0) {
+                diagnostics.push(Diagnostic::at_key(
+                    Severity::Error,
+                    format!("hooks.{}.timeout must be greater than 0", event),
+                    text,
+                    &["hooks", event, "timeout"],
+                ));
+            }
         }
+        diagnostics
+    }
+}
+
+/// Written to `config.yaml` when there is none. Every key is left out, so
+/// the defaults apply until the user sets one.
+pub(crate) const CONFIG_TEMPLATE: &str = "\
+# Settings for mytt. Every key is optional; uncomment one to change it.
+# work_duration: 25
+# short_break_duration: 5
+# long_break_duration: 15
+# sessions_long_break: 4
+# sequence: classic
+# suspend_policy: exclude
+";
+
+/// Merges `update`, a JSON object of top-level keys, into `config.yaml` and
+/// makes the result the active configuration. Mappings such as `hooks` or
+/// `adaptive` are merged key by key. Only the touched lines are rewritten,
+/// so comments and key order survive; a `null` value removes the key.
+/// Nothing is written unless the merged file validates.
+pub fn save_update(update: &str) -> Result<Config> {
+    let update: serde_json::Map<String, serde_json::Value> = serde_json::from_str(update)
+        .map_err(|e| MyttError::Config(format!("update must be a JSON object: {}", e)))?;
+
+    let config_file = Config::file();
+    let mut text = match std::fs::read_to_string(&config_file) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    for (key, value) in &update {
+        text = set_key(&text, key, value)?;
+    }
+
+    let config = ConfigCheck::new(&text).into_result()?;
+    std::fs::create_dir_all(&CONFIG_DIRS[0])?;
+    write_atomically(&config_file, &text)?;
+    if let Ok(mut active) = CONFIG.write() {
+        *active = config.clone();
+    }
+    Ok(config)
+}
+
+/// Makes `name` the sequence to run and saves the choice to `config.yaml`.
+/// Choosing `classic` removes the key.
+pub fn select_sequence(name: &str) -> Result<Config> {
+    let value = match name {
+        Sequence::CLASSIC => serde_json::Value::Null,
+        name => name.into(),
+    };
+    save_update(&serde_json::json!({ "sequence": value }).to_string())
+}
 
-        let config_str = std::fs::read_to_string(config_file).unwrap();
-        let config: Config = serde_yaml::from_str(&config_str).unwrap();
-        config
+/// Sets a top-level `key` in the YAML `text`, keeping everything else as
+/// written, including a trailing comment on the key's own line.
+///
+/// A mapping is merged into the mapping already there, key by key, so
+/// comments on the lines it leaves alone survive; a `null` inside it removes
+/// that key. Sequences and scalars replace what was there.
+pub(crate) fn set_key(text: &str, key: &str, value: &serde_json::Value) -> Result<String> {
+    let known = serde_json::to_value(Config::default())?;
+    let is_known = known.get(key).is_some() || KEY_ALIASES.iter().any(|a| a.contains(&key));
+    if !is_known {
+        return Err(MyttError::Config(format!("unknown key `{}`", key)));
     }
 
-    pub fn save(&self) {
-        let config_file = CONFIG_DIRS[0].join("config.yaml");
-        let config_str = serde_yaml::to_string(&self).unwrap();
-        std::fs::write(config_file, config_str).unwrap();
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+    // Prefer whichever spelling of the key the user already has.
+    let spellings = KEY_ALIASES
+        .iter()
+        .find(|a| a.contains(&key))
+        .map(|a| a.to_vec())
+        .unwrap_or_else(|| vec![key]);
+    let key = spellings
+        .iter()
+        .find(|k| key_line(&lines, 0..lines.len(), 0, k).is_some())
+        .copied()
+        .unwrap_or(key);
+    let end = lines.len();
+    set_in(&mut lines, 0..end, 0, key, value)?;
+
+    let mut text = lines.join("\n");
+    if !text.is_empty() {
+        text.push('\n');
     }
+    Ok(text)
+}
+
+/// Sets `key` in the block mapping indented by `indent` that takes up
+/// `block` of `lines`.
+fn set_in(
+    lines: &mut Vec<String>,
+    block: std::ops::Range<usize>,
+    indent: usize,
+    key: &str,
+    value: &serde_json::Value,
+) -> Result<()> {
+    let Some(i) = key_line(lines, block.clone(), indent, key) else {
+        let rendered = render(key, &without_nulls(value), indent, "")?;
+        lines.splice(block.end..block.end, rendered);
+        return Ok(());
+    };
+    let end = entry_end(lines, i, indent);
+    let rest = key_value(&lines[i], indent, key).unwrap_or_default();
+    let comment = trailing_comment(rest).unwrap_or_default().to_string();
+    let is_block = rest.trim_start().is_empty() || rest.trim_start().starts_with('#');
+    let child_indent = lines[i + 1..end]
+        .iter()
+        .find(|l| !l.trim().is_empty() && !l.trim_start().starts_with('#'))
+        .map(|l| indentation(l))
+        .filter(|child| *child > indent);
+
+    match (value, child_indent) {
+        (serde_json::Value::Null, _) => {
+            lines.drain(i..end);
+        }
+        (serde_json::Value::Object(entries), Some(child_indent))
+            if is_block && !is_sequence(&lines[i + 1..end], child_indent) =>
+        {
+            for (child, value) in entries {
+                let end = entry_end(lines, i, indent);
+                set_in(lines, i + 1..end, child_indent, child, value)?;
+            }
+        }
+        (serde_json::Value::Object(_), _) => {
+            // A flow mapping or anything else: merge into what it holds and
+            // write the result out again.
+            let written: String = lines[i..end]
+                .iter()
+                .map(|l| format!("{}\n", l.get(indent..).unwrap_or_default()))
+                .collect();
+            let existing = serde_yaml::from_str::<serde_json::Value>(&written)
+                .ok()
+                .and_then(|mut v| v.get_mut(key).map(serde_json::Value::take))
+                .unwrap_or_default();
+            let rendered = render(key, &merge(existing, value), indent, &comment)?;
+            lines.splice(i..end, rendered);
+        }
+        (value, _) => {
+            let rendered = render(key, value, indent, &comment)?;
+            lines.splice(i..end, rendered);
+        }
+    }
+    Ok(())
+}
+
+/// Index of the line holding `key` in the block mapping indented by
+/// `indent` within `block`.
+fn key_line(
+    lines: &[String],
+    block: std::ops::Range<usize>,
+    indent: usize,
+    key: &str,
+) -> Option<usize> {
+    block
+        .into_iter()
+        .find(|&i| indentation(&lines[i]) == indent && key_value(&lines[i], indent, key).is_some())
+}
+
+/// The line after the last one that belongs to the entry starting at line
+/// `i`: lines indented deeper, and `- ` items at the same indentation.
+/// Blank lines and comments after the entry are left to what follows.
+fn entry_end(lines: &[String], i: usize, indent: usize) -> usize {
+    let mut end = i + 1;
+    for (j, line) in lines.iter().enumerate().skip(i + 1) {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let deeper = indentation(line) > indent;
+        let item = indentation(line) == indent && trimmed.starts_with("- ");
+        if !deeper && !item {
+            break;
+        }
+        if !trimmed.starts_with('#') || deeper {
+            end = j + 1;
+        }
+    }
+    end
+}
+
+/// Whether the block whose entries sit at `indent` is a sequence.
+fn is_sequence(block: &[String], indent: usize) -> bool {
+    block
+        .iter()
+        .any(|l| indentation(l) == indent && l.trim_start().starts_with("- "))
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// Returns what follows `key:` when `line` is that key at `indent`.
+fn key_value<'a>(line: &'a str, indent: usize, key: &str) -> Option<&'a str> {
+    let rest = line
+        .get(indent..)?
+        .strip_prefix(key)?
+        .trim_start_matches([' ', '\t']);
+    rest.strip_prefix(':')
+        .filter(|value| value.is_empty() || value.starts_with([' ', '\t']))
+}
+
+/// The lines for `key: value` at `indent`, with `comment` after the value,
+/// or after the key when the value is a block. Nothing for `null`.
+fn render(
+    key: &str,
+    value: &serde_json::Value,
+    indent: usize,
+    comment: &str,
+) -> Result<Vec<String>> {
+    let pad = " ".repeat(indent);
+    let is_block = match value {
+        serde_json::Value::Null => return Ok(Vec::new()),
+        serde_json::Value::Object(map) => !map.is_empty(),
+        serde_json::Value::Array(items) => !items.is_empty(),
+        _ => false,
+    };
+    let rendered = serde_yaml::to_string(value)?;
+    if !is_block {
+        return Ok(vec![format!(
+            "{}{}: {}{}",
+            pad,
+            key,
+            rendered.trim_end(),
+            comment
+        )]);
+    }
+    let mut lines = vec![format!("{}{}:{}", pad, key, comment)];
+    lines.extend(rendered.lines().map(|l| format!("{}  {}", pad, l)));
+    Ok(lines)
+}
+
+/// `update` merged into `existing`, mapping by mapping. A `null` in
+/// `update` removes the key.
+fn merge(existing: serde_json::Value, update: &serde_json::Value) -> serde_json::Value {
+    match (existing, update) {
+        (serde_json::Value::Object(mut map), serde_json::Value::Object(update)) => {
+            for (key, value) in update {
+                match value {
+                    serde_json::Value::Null => {
+                        map.remove(key);
+                    }
+                    value => {
+                        let existing = map.remove(key).unwrap_or_default();
+                        map.insert(key.clone(), merge(existing, value));
+                    }
+                }
+            }
+            serde_json::Value::Object(map)
+        }
+        (_, update) => without_nulls(update),
+    }
+}
+
+fn without_nulls(value: &serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => serde_json::Value::Object(
+            map.iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(key, value)| (key.clone(), without_nulls(value)))
+                .collect(),
+        ),
+        value => value.clone(),
+    }
+}
+
+/// Finds a ` # comment` after a scalar, ignoring `#` inside quotes. The
+/// returned slice keeps the whitespace in front of the `#`.
+fn trailing_comment(value: &str) -> Option<&str> {
+    let mut quote = None;
+    let mut prev = ' ';
+    for (i, c) in value.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, '#') if prev.is_whitespace() => {
+                let start = value[..i].trim_end().len();
+                return Some(&value[start..]);
+            }
+            _ => {}
+        }
+        prev = c;
+    }
+    None
+}
+
+/// Writes through a temporary file in the same directory so a crash never
+/// leaves a half-written file behind.
+fn write_atomically(path: &std::path::Path, contents: &str) -> Result<()> {
+    use std::io::Write;
+
+    let tmp = path.with_extension("yaml.tmp");
+    let mut file = std::fs::File::create(&tmp)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Returns a copy of the active configuration.
+pub fn current() -> Config {
+    CONFIG.read().map(|c| c.clone()).unwrap_or_default()
+}
+
+/// Re-reads `config.yaml`. On success it becomes the active configuration;
+/// otherwise the error is reported and the last good configuration is kept.
+pub fn reload() -> Config {
+    match Config::load() {
+        Ok(config) => {
+            if let Ok(mut active) = CONFIG.write() {
+                *active = config.clone();
+            }
+            config
+        }
+        Err(e) => {
+            notify_on_error!(format!("{} (keeping the previous configuration)", e));
+            current()
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem found in `config.yaml`, with a 1-based position when known.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl Diagnostic {
+    fn at_key(severity: Severity, message: String, text: &str, key_path: &[&str]) -> Self {
+        let position = find_key(text, key_path);
+        Self {
+            severity,
+            message,
+            line: position.map(|(line, _)| line),
+            column: position.map(|(_, column)| column),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "config.yaml")?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+            if let Some(column) = self.column {
+                write!(f, ":{}", column)?;
+            }
+        }
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, ": {}: {}", severity, self.message)
+    }
+}
+
+/// The outcome of parsing and validating a configuration file.
+#[derive(Debug, Serialize)]
+pub struct ConfigCheck {
+    pub valid: bool,
+    pub diagnostics: Vec<Diagnostic>,
+    #[serde(skip)]
+    config: Option<Config>,
+}
+
+impl ConfigCheck {
+    pub fn new(text: &str) -> Self {
+        let mut diagnostics = Vec::new();
+        let config = if text.trim().is_empty() {
+            Some(Config::default())
+        } else {
+            let mut unknown = Vec::new();
+            let parsed =
+                serde_ignored::deserialize(serde_yaml::Deserializer::from_str(text), |path| {
+                    unknown.push(key_path(&path))
+                });
+            for path in unknown {
+                let keys: Vec<&str> = path.iter().map(String::as_str).collect();
+                diagnostics.push(Diagnostic::at_key(
+                    Severity::Warning,
+                    format!("unknown key `{}` is ignored", path.join(".")),
+                    text,
+                    &keys,
+                ));
+            }
+            match parsed {
+                Ok(config) => Some(config),
+                Err(e) => {
+                    let location = e.location();
+                    diagnostics.push(Diagnostic {
+                        severity: Severity::Error,
+                        message: e.to_string(),
+                        line: location.as_ref().map(|l| l.line()),
+                        column: location.as_ref().map(|l| l.column()),
+                    });
+                    None
+                }
+            }
+        };
+        if let Some(config) = &config {
+            diagnostics.extend(config.validate(text));
+        }
+
+        let valid = !diagnostics.iter().any(|d| d.severity == Severity::Error);
+        Self {
+            valid,
+            diagnostics,
+            config: config.filter(|_| valid),
+        }
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Warning)
+    }
+
+    pub fn into_result(self) -> Result<Config> {
+        match self.config {
+            Some(config) => Ok(config),
+            None => Err(MyttError::Config(
+                self.diagnostics
+                    .iter()
+                    .filter(|d| d.severity == Severity::Error)
+                    .map(|d| d.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            )),
+        }
+    }
+}
+
+fn key_path(path: &serde_ignored::Path) -> Vec<String> {
+    use serde_ignored::Path;
+    match path {
+        Path::Root => Vec::new(),
+        Path::Seq { parent, index } => {
+            let mut keys = key_path(parent);
+            keys.push(index.to_string());
+            keys
+        }
+        Path::Map { parent, key } => {
+            let mut keys = key_path(parent);
+            keys.push(key.clone());
+            keys
+        }
+        Path::Some { parent }
+        | Path::NewtypeStruct { parent }
+        | Path::NewtypeVariant { parent } => key_path(parent),
+    }
+}
+
+/// Finds the 1-based line and column of a (possibly nested) mapping key by
+/// walking down the indentation. Sequence indices in the path are skipped.
+fn find_key(text: &str, key_path: &[&str]) -> Option<(usize, usize)> {
+    let mut found = None;
+    let mut lines = text.lines().enumerate();
+    let mut min_indent = 0;
+    for key in key_path.iter().filter(|k| k.parse::<usize>().is_err()) {
+        let (line, column) = lines.find_map(|(i, line)| {
+            let content = line.trim_start().trim_start_matches("- ");
+            let indent = line.len() - content.len();
+            let rest = content.strip_prefix(*key)?.trim_start();
+            (indent >= min_indent && rest.starts_with(':')).then_some((i + 1, indent + 1))
+        })?;
+        min_indent = column;
+        found = Some((line, column));
+    }
+    found
 }
 
 // const THEMES_DIR: &str = "themes";
 
 lazy_static! {
     // static ref KEY_COMBO: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
-    pub static ref CONFIG: Config = Config::load();
+    /// The last configuration that loaded without errors.
+    pub static ref CONFIG: RwLock<Config> = RwLock::new(Config::load_or_default());
     pub static ref HOME_DIR: PathBuf = home_dir().unwrap();
     pub static ref CONFIG_DIRS: Vec<PathBuf> = config_dirs();
     pub static ref APP_NAME: String = env!("CARGO_PKG_NAME").to_string();
@@ -115,20 +951,98 @@ fn scripts_dir() -> PathBuf {
         .unwrap_or_else(|| {
             // mkidr -p $XDG_CONFIG_HOME/tauri/scripts
             let dir = CONFIG_DIRS[0].join("scripts");
-            std::fs::create_dir_all(&dir).unwrap();
+            if let Err(e) = std::fs::create_dir_all(&dir) {
+                notify_on_error!(format!("failed to create {}: {}", dir.display(), e));
+            }
             dir
         })
 }
 
-pub fn get_script(script_name: &str) -> Option<PathBuf> {
-    let path = SCRIPTS_DIR.join(script_name);
-    if !path.exists() {
-        let path_display = path.display();
-        let msg = format!("{} script not found at {}", script_name, path_display).to_string();
+/// Every script to run for `event`, in order: `SCRIPTS_DIR/<event>` first,
+/// then the contents of `SCRIPTS_DIR/<event>.d/`, then the same for the
+/// current segment's `SCRIPTS_DIR/<session type>/`. Hooks are optional, so a
+/// missing one is only reported when it is listed in `required_hooks`.
+pub fn get_scripts(event: &str, session_type: &SessionType) -> Vec<PathBuf> {
+    let config = current();
+    let disabled = config.hook(event).disabled.unwrap_or_default();
+    let scripts: Vec<PathBuf> = script_dirs(&SCRIPTS_DIR, std::slice::from_ref(session_type))
+        .iter()
+        .flat_map(|dir| scripts_in(dir, event, &disabled))
+        .collect();
+    if scripts.is_empty() && config.is_required_hook(event) {
+        let msg = format!("{} script not found at {}", event, SCRIPTS_DIR.display());
         notify_on_error!(msg);
-        return None;
     }
-    Some(path)
+    scripts
+}
+
+/// Where hook scripts are looked up: `scripts_dir` itself, then
+/// `scripts_dir/<session type>/` for each of `session_types`.
+pub(crate) fn script_dirs(
+    scripts_dir: &std::path::Path,
+    session_types: &[SessionType],
+) -> Vec<PathBuf> {
+    std::iter::once(scripts_dir.to_path_buf())
+        .chain(session_types.iter().map(|t| scripts_dir.join(t.as_str())))
+        .collect()
+}
+
+/// Scripts that would run for `event`. Files in `<event>.d/` run in lexical
+/// order of their names, like `run-parts`; those that are not executable or
+/// are named in `disabled` are skipped, and so is a non-executable `<event>`.
+pub(crate) fn scripts_in(dir: &std::path::Path, event: &str, disabled: &[String]) -> Vec<PathBuf> {
+    script_candidates(dir, event)
+        .into_iter()
+        .filter(|path| is_executable(path) || scripting::is_embedded(path))
+        .filter(|path| !is_disabled(path, disabled))
+        .collect()
+}
+
+/// `<event>`, `<event>.rhai` and everything in `<event>.d/` apart from hidden
+/// files and editor backups, whether it would run or not.
+pub(crate) fn script_candidates(dir: &std::path::Path, event: &str) -> Vec<PathBuf> {
+    let embedded = format!("{}.{}", event, scripting::EXTENSION);
+    let mut scripts: Vec<PathBuf> = [dir.join(event), dir.join(embedded)]
+        .into_iter()
+        .filter(|path| path.is_file())
+        .collect();
+
+    if let Ok(entries) = std::fs::read_dir(dir.join(format!("{}.d", event))) {
+        let mut parts: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                let name = path
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or_default();
+                path.is_file() && !name.starts_with('.') && !name.ends_with('~')
+            })
+            .collect();
+        parts.sort();
+        scripts.extend(parts);
+    }
+    scripts
+}
+
+pub(crate) fn is_disabled(path: &std::path::Path, disabled: &[String]) -> bool {
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+    disabled.iter().any(|d| d == name)
+}
+
+pub(crate) fn is_executable(path: &std::path::Path) -> bool {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return false;
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+    }
+    #[cfg(not(unix))]
+    metadata.is_file()
 }
 
 fn home_dir() -> Option<PathBuf> {
@@ -145,8 +1059,8 @@ pub fn xdg_config_home() -> PathBuf {
 }
 
 fn config_dirs() -> Vec<PathBuf> {
-    let mut dirs = Vec::new();
-    dirs.push(xdg_config_home());
+    #[cfg_attr(not(target_os = "macos"), allow(unused_mut))]
+    let mut dirs = vec![xdg_config_home()];
 
     #[cfg(target_os = "macos")]
     if let Some(d) = std::env::var_os("XDG_CONFIG_DIRS") {
@@ -0,0 +1,145 @@
+//! The countdown for the current session.
+//!
+//! The stopwatch counts up; this ticker compares it against the session's
+//! target duration, keeps the frontend in sync through `tick` events and
+//! finishes the session when time runs out. It runs on its own thread so
+//! sessions complete on time even while the window is hidden or throttled.
+use crate::config::{self, SuspendPolicy};
+use crate::error::Result;
+use crate::{
+    check, current_status, format_millis, notify, notify_on_error, run_hook, state_changed, step,
+    AppState, SessionType, Status, Stopwatch, TimerState, Transition,
+};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+const TICK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// What the ticker does with one status reading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Tick {
+    /// The displayed second or the state changed, so the frontend is told.
+    pub emit: bool,
+    /// A running session has reached its planned length.
+    pub complete: bool,
+}
+
+/// The ticker's decisions, kept apart from its thread and the app state so
+/// they can be driven by a fake clock.
+#[derive(Debug, Default)]
+pub(crate) struct Countdown {
+    last: Option<Status>,
+}
+
+impl Countdown {
+    pub(crate) fn tick(&mut self, status: &Status) -> Tick {
+        let emit = self.last.as_ref().is_none_or(|last| {
+            last.state != status.state
+                || last.session_type != status.session_type
+                || last.remaining_ms / 1000 != status.remaining_ms / 1000
+                || last.elapsed_ms / 1000 != status.elapsed_ms / 1000
+        });
+        let complete =
+            status.state == TimerState::Running && !status.open_ended && status.remaining_ms == 0;
+        self.last = Some(status.clone());
+        Tick { emit, complete }
+    }
+}
+
+pub fn spawn(handle: AppHandle) {
+    thread::spawn(move || {
+        let mut countdown = Countdown::default();
+        loop {
+            thread::sleep(TICK_INTERVAL);
+            if let Err(e) = check_suspend(&handle) {
+                notify_on_error!(e);
+            }
+            let status = match current_status() {
+                Ok(status) => status,
+                Err(e) => {
+                    eprintln!("mytt: countdown: {}", e);
+                    continue;
+                }
+            };
+
+            let tick = countdown.tick(&status);
+            if tick.emit {
+                let _ = handle.emit("tick", &status);
+            }
+            if tick.complete {
+                if let Err(e) = complete(&handle, &status) {
+                    notify_on_error!(e);
+                }
+            }
+        }
+    });
+}
+
+/// Applies the configured policy to a suspend the running stopwatch slept
+/// through. Checked every tick, so a pause lands within a tick of the wake.
+fn check_suspend(handle: &AppHandle) -> Result<()> {
+    let policy = config::current().suspend_policy();
+    let gap = {
+        let stopwatch = handle.state::<Mutex<Stopwatch>>();
+        let mut stopwatch = stopwatch.lock()?;
+        let Some(gap) = stopwatch.detect_suspend() else {
+            return Ok(());
+        };
+        stopwatch.apply_suspend(gap, policy);
+        gap
+    };
+    if policy != SuspendPolicy::Pause {
+        state_changed();
+        return Ok(());
+    }
+    // The stopwatch is already paused; bring the session along, with the
+    // hook running first as it does for any other pause.
+    check(Transition::Pause)?;
+    run_hook("on_pause")?;
+    step(Transition::Pause)?;
+    notify!(format!(
+        "Paused the session while the computer slept for {}.",
+        format_millis(gap.as_millis())
+    ));
+    handle.emit("suspend_paused", gap.as_millis() as u64)?;
+    Ok(())
+}
+
+/// Runs the `on_complete` hook, stops the clock at zero, then notifies the
+/// user and the frontend with the segment's sound. A finished work session
+/// waits for its rating; a finished break moves on to the next segment.
+fn complete(handle: &AppHandle, status: &Status) -> Result<()> {
+    // The hook sees the session that finished, before a break moves on.
+    run_hook("on_complete")?;
+    let segment = handle
+        .state::<Mutex<AppState>>()
+        .lock()?
+        .session
+        .segment()
+        .clone();
+    step(Transition::Complete)?;
+
+    let message = match &segment.session_type {
+        SessionType::Work => "Work session complete! Time for a break.".to_string(),
+        SessionType::ShortBreak | SessionType::LongBreak => {
+            "Break is over. Ready to get back to work?".to_string()
+        }
+        SessionType::Custom(name) => format!("{} is over.", name),
+    };
+    notify_with_sound(handle, &message, segment.sound.as_deref());
+
+    handle.emit("session_completed", status)?;
+    Ok(())
+}
+
+fn notify_with_sound(handle: &AppHandle, message: &str, sound: Option<&str>) {
+    use tauri_plugin_notification::NotificationExt;
+    let builder = handle.notification().builder().title("mytt").body(message);
+    let builder = match sound {
+        Some(sound) => builder.sound(sound),
+        None => builder,
+    };
+    builder.show().unwrap_or_else(|e| eprintln!("{}", e));
+}
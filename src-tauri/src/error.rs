@@ -0,0 +1,80 @@
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::sync::PoisonError;
+
+/// Errors surfaced to the frontend by every `#[tauri::command]`.
+///
+/// They serialise as `{ "kind": "config", "message": "..." }` so the UI can
+/// react to the category without parsing the message.
+#[derive(Debug, thiserror::Error)]
+pub enum MyttError {
+    #[error("config: {0}")]
+    Config(String),
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("script: {0}")]
+    Script(String),
+    #[error("state: {0}")]
+    State(String),
+    #[error("journal: {0}")]
+    Journal(String),
+    #[error("app: {0}")]
+    Tauri(#[from] tauri::Error),
+    /// A hook refused the requested action.
+    #[error("vetoed: {0}")]
+    Vetoed(String),
+}
+
+pub type Result<T, E = MyttError> = std::result::Result<T, E>;
+
+impl MyttError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            MyttError::Config(_) => "config",
+            MyttError::Io(_) => "io",
+            MyttError::Script(_) => "script",
+            MyttError::State(_) => "state",
+            MyttError::Journal(_) => "journal",
+            MyttError::Tauri(_) => "app",
+            MyttError::Vetoed(_) => "vetoed",
+        }
+    }
+}
+
+impl<T> From<PoisonError<T>> for MyttError {
+    fn from(e: PoisonError<T>) -> Self {
+        MyttError::State(e.to_string())
+    }
+}
+
+impl From<rusqlite::Error> for MyttError {
+    fn from(e: rusqlite::Error) -> Self {
+        MyttError::Journal(e.to_string())
+    }
+}
+
+impl From<serde_yaml::Error> for MyttError {
+    fn from(e: serde_yaml::Error) -> Self {
+        MyttError::Config(e.to_string())
+    }
+}
+
+impl From<notify::Error> for MyttError {
+    fn from(e: notify::Error) -> Self {
+        MyttError::Config(format!("cannot watch config directory: {}", e))
+    }
+}
+
+impl From<serde_json::Error> for MyttError {
+    fn from(e: serde_json::Error) -> Self {
+        MyttError::State(e.to_string())
+    }
+}
+
+impl Serialize for MyttError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("MyttError", 2)?;
+        error.serialize_field("kind", self.kind())?;
+        error.serialize_field("message", &self.to_string())?;
+        error.end()
+    }
+}
@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+
+/// The application that currently has keyboard focus.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FrontmostApp {
+    pub name: String,
+    pub title: Option<String>,
+}
+
+#[cfg(target_os = "linux")]
+pub use linux::frontmost_application;
+
+/// Parses the reply to a Hyprland `j/activewindow` request.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub(crate) fn parse_hyprland_active_window(reply: &str) -> Option<FrontmostApp> {
+    let window: serde_json::Value = serde_json::from_str(reply).ok()?;
+    let name = window.get("class")?.as_str().filter(|s| !s.is_empty())?;
+    Some(FrontmostApp {
+        name: name.to_string(),
+        title: window
+            .get("title")
+            .and_then(|t| t.as_str())
+            .map(str::to_string),
+    })
+}
+
+/// Finds the focused node in a sway `GET_TREE` reply.
+///
+/// Native Wayland clients report `app_id`, XWayland ones only carry the X11
+/// `WM_CLASS` in `window_properties`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub(crate) fn parse_sway_tree(tree: &serde_json::Value) -> Option<FrontmostApp> {
+    if tree.get("focused").and_then(|f| f.as_bool()) == Some(true) {
+        let name = tree
+            .get("app_id")
+            .and_then(|a| a.as_str())
+            .or_else(|| tree.pointer("/window_properties/class")?.as_str())?;
+        return Some(FrontmostApp {
+            name: name.to_string(),
+            title: tree
+                .get("name")
+                .and_then(|n| n.as_str())
+                .map(str::to_string),
+        });
+    }
+
+    ["nodes", "floating_nodes"]
+        .iter()
+        .filter_map(|key| tree.get(key)?.as_array())
+        .flatten()
+        .find_map(parse_sway_tree)
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::{parse_hyprland_active_window, parse_sway_tree, FrontmostApp};
+    use std::env;
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixStream;
+    use std::path::{Path, PathBuf};
+
+    enum Backend {
+        Sway(PathBuf),
+        Hyprland(PathBuf),
+        X11,
+    }
+
+    impl Backend {
+        /// Picks the backend for the session we are running in. Compositor
+        /// sockets win over `DISPLAY`, which is also set under XWayland and
+        /// would only ever see X11 clients.
+        fn from_env() -> Option<Self> {
+            if let Some(sock) = env::var_os("SWAYSOCK") {
+                return Some(Backend::Sway(PathBuf::from(sock)));
+            }
+            if let Some(signature) = env::var_os("HYPRLAND_INSTANCE_SIGNATURE") {
+                let runtime_dir = env::var_os("XDG_RUNTIME_DIR")
+                    .map(|dir| PathBuf::from(dir).join("hypr"))
+                    .filter(|dir| dir.join(&signature).exists())
+                    .unwrap_or_else(|| PathBuf::from("/tmp/hypr"));
+                let sock = runtime_dir.join(signature).join(".socket.sock");
+                return Some(Backend::Hyprland(sock));
+            }
+            if env::var_os("DISPLAY").is_some() {
+                return Some(Backend::X11);
+            }
+            None
+        }
+    }
+
+    pub fn frontmost_application() -> Option<FrontmostApp> {
+        let result = match Backend::from_env()? {
+            Backend::Sway(sock) => sway(&sock),
+            Backend::Hyprland(sock) => hyprland(&sock),
+            Backend::X11 => x11(),
+        };
+        result.unwrap_or_else(|e| {
+            eprintln!("mytt: failed to detect frontmost application: {}", e);
+            None
+        })
+    }
+
+    const I3_IPC_MAGIC: &[u8] = b"i3-ipc";
+    const I3_IPC_GET_TREE: u32 = 4;
+
+    fn sway(sock: &Path) -> anyhow::Result<Option<FrontmostApp>> {
+        let mut stream = UnixStream::connect(sock)?;
+        let mut request = I3_IPC_MAGIC.to_vec();
+        request.extend_from_slice(&0u32.to_ne_bytes());
+        request.extend_from_slice(&I3_IPC_GET_TREE.to_ne_bytes());
+        stream.write_all(&request)?;
+
+        let mut header = [0u8; 14];
+        stream.read_exact(&mut header)?;
+        if &header[..6] != I3_IPC_MAGIC {
+            anyhow::bail!("unexpected reply from sway");
+        }
+        let len = u32::from_ne_bytes(header[6..10].try_into()?) as usize;
+        let mut payload = vec![0u8; len];
+        stream.read_exact(&mut payload)?;
+
+        let tree: serde_json::Value = serde_json::from_slice(&payload)?;
+        Ok(parse_sway_tree(&tree))
+    }
+
+    fn hyprland(sock: &Path) -> anyhow::Result<Option<FrontmostApp>> {
+        let mut stream = UnixStream::connect(sock)?;
+        stream.write_all(b"j/activewindow")?;
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply)?;
+        Ok(parse_hyprland_active_window(&reply))
+    }
+
+    fn x11() -> anyhow::Result<Option<FrontmostApp>> {
+        use x11rb::connection::Connection;
+        use x11rb::properties::WmClass;
+        use x11rb::protocol::xproto::{AtomEnum, ConnectionExt};
+
+        let (conn, screen) = x11rb::connect(None)?;
+        let root = conn.setup().roots[screen].root;
+        let atom = |name: &[u8]| -> anyhow::Result<u32> {
+            Ok(conn.intern_atom(false, name)?.reply()?.atom)
+        };
+        let net_active_window = atom(b"_NET_ACTIVE_WINDOW")?;
+        let net_wm_name = atom(b"_NET_WM_NAME")?;
+        let utf8_string = atom(b"UTF8_STRING")?;
+
+        let active = conn
+            .get_property(false, root, net_active_window, AtomEnum::WINDOW, 0, 1)?
+            .reply()?;
+        let Some(window) = active.value32().and_then(|mut ids| ids.next()) else {
+            return Ok(None);
+        };
+        if window == 0 {
+            return Ok(None);
+        }
+
+        let Some(class) = WmClass::get(&conn, window)?.reply()? else {
+            return Ok(None);
+        };
+        let name = String::from_utf8_lossy(class.class()).into_owned();
+
+        let mut title = conn
+            .get_property(false, window, net_wm_name, utf8_string, 0, u32::MAX)?
+            .reply()?
+            .value;
+        if title.is_empty() {
+            title = conn
+                .get_property(
+                    false,
+                    window,
+                    AtomEnum::WM_NAME,
+                    AtomEnum::STRING,
+                    0,
+                    u32::MAX,
+                )?
+                .reply()?
+                .value;
+        }
+
+        Ok(Some(FrontmostApp {
+            name,
+            title: Some(String::from_utf8_lossy(&title).into_owned()).filter(|t| !t.is_empty()),
+        }))
+    }
+}
@@ -0,0 +1,846 @@
+//! What a hook script gets to know about the event that triggered it.
+//!
+//! Every hook receives the same context twice: as `MYTT_*` environment
+//! variables for shell one-liners, and as a JSON object on stdin for
+//! anything richer. The positional arguments older scripts rely on are still
+//! passed: `$1` is the formatted elapsed time and `$2` the frontmost app.
+//!
+//! Hooks run one at a time through [`CommandExecutor`], each in its own
+//! process group so a timeout or shutdown can take down whatever the script
+//! spawned as well. `*.rhai` hooks go through the same queue but run in the
+//! embedded engine from [`crate::scripting`].
+use crate::adaptive::{FocusRating, FocusStats};
+use crate::config::{self, Config, HookSettings, CACHE_DIR};
+use crate::error::{MyttError, Result};
+use crate::frontmost::FrontmostApp;
+use crate::scripting;
+use crate::{
+    format_millis, notify, notify_on_error, Lap, Phase, Session, SessionType, Stopwatch, Transition,
+};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, sync_channel, Receiver, Sender, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Every event that runs hooks.
+pub const EVENTS: &[&str] = &[
+    "on_start",
+    "on_pause",
+    "on_resume",
+    "on_stop",
+    "on_log",
+    "on_complete",
+    "on_skip_break",
+    "on_transition",
+    "on_rate",
+];
+
+// Events that end the session being timed, so their context has an end time.
+const ENDING_EVENTS: &[&str] = &["on_stop", "on_log", "on_complete"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HookContext {
+    pub event: String,
+    pub session_type: SessionType,
+    pub phase: Phase,
+    /// The transition that fired `on_transition`.
+    pub transition: Option<Transition>,
+    pub elapsed_ms: u64,
+    pub planned_seconds: u64,
+    pub started_at: Option<DateTime<Local>>,
+    pub ended_at: Option<DateTime<Local>>,
+    /// Work sessions completed since the app started.
+    pub work_sessions: u64,
+    pub focus_rating: Option<String>,
+    /// Note given with the focus rating.
+    #[serde(default)]
+    pub note: Option<String>,
+    /// The flow streak and daily goal, once a rating has been recorded.
+    #[serde(default)]
+    pub focus: Option<FocusStats>,
+    pub app_name: Option<String>,
+    pub window_title: Option<String>,
+    /// Splits taken in the session so far.
+    #[serde(default)]
+    pub laps: Vec<Lap>,
+}
+
+impl HookContext {
+    pub fn new(event: &str, stopwatch: &Stopwatch, session: &Session) -> Self {
+        Self {
+            event: event.to_string(),
+            session_type: session.current_session(),
+            phase: session.phase(),
+            transition: None,
+            elapsed_ms: stopwatch.elapsed() as u64,
+            planned_seconds: session.target_duration(),
+            started_at: stopwatch.started_at(),
+            ended_at: ENDING_EVENTS.contains(&event).then(Local::now),
+            work_sessions: session.work_sessions,
+            focus_rating: None,
+            note: None,
+            focus: None,
+            app_name: None,
+            window_title: None,
+            laps: stopwatch.laps().to_vec(),
+        }
+    }
+
+    /// Adds a focus rating given for the session, with its note and the
+    /// stats it leads to.
+    pub fn with_rating(
+        mut self,
+        rating: FocusRating,
+        note: Option<String>,
+        focus: FocusStats,
+    ) -> Self {
+        self.focus_rating = Some(rating.as_str().to_string());
+        self.note = note;
+        self.focus = Some(focus);
+        self
+    }
+
+    pub fn with_frontmost(mut self, app: Option<FrontmostApp>) -> Self {
+        if let Some(app) = app {
+            self.app_name = Some(app.name);
+            self.window_title = app.title;
+        }
+        self
+    }
+
+    /// The `MYTT_*` variables, documented in the README. Unknown values are
+    /// passed as empty strings so scripts can rely on every name being set.
+    pub fn env(&self) -> Vec<(&'static str, String)> {
+        let timestamp = |t: &Option<DateTime<Local>>| t.map(|t| t.to_rfc3339()).unwrap_or_default();
+        let focus = |stat: fn(&FocusStats) -> u32| {
+            self.focus
+                .as_ref()
+                .map(|focus| stat(focus).to_string())
+                .unwrap_or_default()
+        };
+        vec![
+            ("MYTT_EVENT", self.event.clone()),
+            ("MYTT_SESSION_TYPE", self.session_type.as_str().to_string()),
+            ("MYTT_PHASE", self.phase.as_str().to_string()),
+            (
+                "MYTT_TRANSITION",
+                self.transition
+                    .map(|transition| transition.as_str().to_string())
+                    .unwrap_or_default(),
+            ),
+            ("MYTT_ELAPSED_SECONDS", (self.elapsed_ms / 1000).to_string()),
+            ("MYTT_PLANNED_SECONDS", self.planned_seconds.to_string()),
+            ("MYTT_STARTED_AT", timestamp(&self.started_at)),
+            ("MYTT_ENDED_AT", timestamp(&self.ended_at)),
+            ("MYTT_WORK_SESSIONS", self.work_sessions.to_string()),
+            (
+                "MYTT_FOCUS_RATING",
+                self.focus_rating.clone().unwrap_or_default(),
+            ),
+            ("MYTT_NOTE", self.note.clone().unwrap_or_default()),
+            ("MYTT_FLOW_STREAK", focus(|focus| focus.streak)),
+            ("MYTT_FLOW_TODAY", focus(|focus| focus.flow_today)),
+            ("MYTT_DAILY_FLOW_GOAL", focus(|focus| focus.daily_goal)),
+            ("MYTT_APP_NAME", self.app_name.clone().unwrap_or_default()),
+            (
+                "MYTT_WINDOW_TITLE",
+                self.window_title.clone().unwrap_or_default(),
+            ),
+        ]
+    }
+
+    /// Runs `script` with this context and waits for it to exit, killing its
+    /// process group once `timeout` has passed.
+    pub fn run(
+        &self,
+        script: &Path,
+        timeout: Option<Duration>,
+        running: &RunningHooks,
+    ) -> io::Result<HookOutput> {
+        let mut command = Command::new(script);
+        command
+            .arg(format_millis(self.elapsed_ms as u128))
+            .arg(self.app_name.as_deref().unwrap_or("Unknown"))
+            .envs(self.env())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            command.process_group(0);
+        }
+        let mut child = command.spawn()?;
+        let _registration = running.register(&child);
+
+        // A script that never reads stdin would block a large write, so the
+        // context goes in from another thread and the timeout still applies.
+        let stdin = write_in_background(child.stdin.take(), serde_json::to_vec(self)?);
+        let stdout = read_in_background(child.stdout.take());
+        let stderr = read_in_background(child.stderr.take());
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut timed_out = false;
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                timed_out = true;
+                break terminate(&mut child)?;
+            }
+            thread::sleep(POLL_INTERVAL);
+        };
+        if let Ok(Err(e)) = stdin.try_recv() {
+            return Err(e);
+        }
+
+        // Anything the script left running in the background keeps its pipes
+        // open, so the same deadline applies to collecting the output.
+        let pid = child.id();
+        let mut collect = |receiver: Receiver<Vec<u8>>| {
+            let wait = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+            let received = match wait {
+                Some(wait) => receiver.recv_timeout(wait).ok(),
+                None => receiver.recv().ok(),
+            };
+            received.unwrap_or_else(|| {
+                timed_out = true;
+                signal_group(pid, Signal::Kill);
+                receiver.recv_timeout(KILL_GRACE).unwrap_or_default()
+            })
+        };
+        let output = Output {
+            status,
+            stdout: collect(stdout),
+            stderr: collect(stderr),
+        };
+        Ok(HookOutput { output, timed_out })
+    }
+}
+
+/// A finished hook process.
+pub struct HookOutput {
+    pub output: Output,
+    /// Whether the process group had to be killed for running too long.
+    pub timed_out: bool,
+}
+
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+// How long a timed-out hook gets to exit after SIGTERM before it is killed.
+const KILL_GRACE: Duration = Duration::from_secs(2);
+
+/// Writes `payload` to `pipe` and closes it. Scripts that never read stdin
+/// may exit before we finish writing, which is not an error.
+fn write_in_background(
+    pipe: Option<impl Write + Send + 'static>,
+    payload: Vec<u8>,
+) -> Receiver<io::Result<()>> {
+    let (sender, receiver) = channel();
+    if let Some(mut pipe) = pipe {
+        thread::spawn(move || {
+            let result = match pipe.write_all(&payload) {
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+                result => result,
+            };
+            let _ = sender.send(result);
+        });
+    }
+    receiver
+}
+
+fn read_in_background(pipe: Option<impl Read + Send + 'static>) -> Receiver<Vec<u8>> {
+    let (sender, receiver) = channel();
+    if let Some(mut pipe) = pipe {
+        thread::spawn(move || {
+            let mut buffer = Vec::new();
+            let _ = pipe.read_to_end(&mut buffer);
+            let _ = sender.send(buffer);
+        });
+    } else {
+        let _ = sender.send(Vec::new());
+    }
+    receiver
+}
+
+/// Asks the hook's process group to exit, then kills it if it does not.
+fn terminate(child: &mut Child) -> io::Result<ExitStatus> {
+    #[cfg(unix)]
+    {
+        signal_group(child.id(), Signal::Term);
+        let deadline = Instant::now() + KILL_GRACE;
+        while Instant::now() < deadline {
+            if let Some(status) = child.try_wait()? {
+                return Ok(status);
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+        signal_group(child.id(), Signal::Kill);
+    }
+    #[cfg(not(unix))]
+    child.kill()?;
+    child.wait()
+}
+
+#[derive(Clone, Copy)]
+enum Signal {
+    #[cfg_attr(not(unix), allow(dead_code))]
+    Term,
+    Kill,
+}
+
+/// Sends `signal` to every process in the group led by `pid`. Elsewhere than
+/// on Unix there are no process groups, and only the script itself is killed.
+fn signal_group(pid: u32, signal: Signal) {
+    #[cfg(unix)]
+    {
+        let signal = match signal {
+            Signal::Term => libc::SIGTERM,
+            Signal::Kill => libc::SIGKILL,
+        };
+        // Hooks are spawned with `process_group(0)`, so their pid is the
+        // group id and a negative pid addresses the whole group.
+        unsafe {
+            libc::kill(-(pid as libc::pid_t), signal);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = (pid, signal);
+}
+
+/// The process groups of the hooks running right now.
+#[derive(Default)]
+pub struct RunningHooks {
+    groups: Mutex<HashSet<u32>>,
+    cancelled: AtomicBool,
+    // Bumped by `kill_all`, for embedded scripts that have no process.
+    kills: Arc<AtomicUsize>,
+}
+
+impl RunningHooks {
+    fn register(&self, child: &Child) -> Registration<'_> {
+        if let Ok(mut groups) = self.groups.lock() {
+            groups.insert(child.id());
+        }
+        // Lost the race with `cancel`: the group was not there to be killed.
+        if self.is_cancelled() {
+            signal_group(child.id(), Signal::Kill);
+        }
+        Registration {
+            running: self,
+            pid: child.id(),
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Returns whether `kill_all` has been called since.
+    pub(crate) fn watch_kills(&self) -> impl Fn() -> bool + 'static {
+        let kills = Arc::clone(&self.kills);
+        let seen = kills.load(Ordering::SeqCst);
+        move || kills.load(Ordering::SeqCst) != seen
+    }
+
+    pub fn kill_all(&self) {
+        self.kills.fetch_add(1, Ordering::SeqCst);
+        if let Ok(groups) = self.groups.lock() {
+            for pid in groups.iter() {
+                signal_group(*pid, Signal::Kill);
+            }
+        }
+    }
+
+    /// Kills the running hooks and keeps any further ones from starting.
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.kill_all();
+    }
+}
+
+struct Registration<'a> {
+    running: &'a RunningHooks,
+    pid: u32,
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut groups) = self.running.groups.lock() {
+            groups.remove(&self.pid);
+        }
+    }
+}
+
+// Captured output beyond this many bytes per stream is dropped from the log.
+const MAX_OUTPUT_BYTES: usize = 4096;
+// The log is rotated to `hooks.log.1` once it grows past this size.
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+
+/// The outcome of one hook script run.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HookRun {
+    pub event: String,
+    pub script: PathBuf,
+    pub started_at: DateTime<Local>,
+    pub duration_ms: u64,
+    /// `None` when the script could not be started or was killed by a signal.
+    pub exit_code: Option<i32>,
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+    /// Why the script could not be started, if it could not.
+    pub error: Option<String>,
+    #[serde(default)]
+    pub timed_out: bool,
+    /// What a successful script asked the app to do.
+    #[serde(default)]
+    pub response: Option<HookResponse>,
+}
+
+/// Instructions a hook can hand back by printing a JSON object as the last
+/// line of its stdout, e.g. `{"note": "Reviewed PR", "tags": ["review"]}`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct HookResponse {
+    /// Note to store with the session being logged.
+    pub note: Option<String>,
+    /// Tags to store with the session being logged.
+    pub tags: Vec<String>,
+    /// Length of the next session in seconds.
+    pub next_duration: Option<u64>,
+    /// Refuse the action, currently only honoured by `on_skip_break`.
+    pub veto: bool,
+    /// Why the action was refused.
+    pub reason: Option<String>,
+    /// Message to show as a notification.
+    pub notify: Option<String>,
+}
+
+impl HookResponse {
+    /// Reads the response from a script's stdout. Anything before the last
+    /// line is ordinary output.
+    pub fn parse(stdout: &str) -> Option<Self> {
+        let last = stdout.lines().rev().find(|line| !line.trim().is_empty())?;
+        let last = last.trim();
+        if !last.starts_with('{') {
+            return None;
+        }
+        serde_json::from_str(last).ok()
+    }
+
+    /// Combines the responses of several scripts run for one event: notes
+    /// are joined, tags collected, and the last requested duration wins.
+    pub fn merge(&mut self, other: HookResponse) {
+        self.note = match (self.note.take(), other.note) {
+            (Some(note), Some(other)) => Some(format!("{}\n{}", note, other)),
+            (note, other) => note.or(other),
+        };
+        for tag in other.tags {
+            if !self.tags.contains(&tag) {
+                self.tags.push(tag);
+            }
+        }
+        self.next_duration = other.next_duration.or(self.next_duration);
+        if other.veto && !self.veto {
+            self.veto = true;
+            self.reason = other.reason;
+        }
+        // Notifications are shown as each script finishes.
+        self.notify = None;
+    }
+}
+
+impl HookRun {
+    /// Runs `script` for `context` and records how it went.
+    pub fn record(
+        script: &Path,
+        context: &HookContext,
+        timeout: Option<Duration>,
+        running: &RunningHooks,
+    ) -> Self {
+        let started_at = Local::now();
+        let started = Instant::now();
+        if scripting::is_embedded(script) {
+            let output = scripting::run(script, context, timeout, running);
+            // Scripts that fail exit with 1 like a shell would; stopped ones
+            // have no status at all.
+            let exit_code = match (output.terminated, &output.error) {
+                (true, _) => None,
+                (false, Some(_)) => Some(1),
+                (false, None) => Some(0),
+            };
+            return Self {
+                event: context.event.clone(),
+                script: script.to_path_buf(),
+                started_at,
+                duration_ms: started.elapsed().as_millis() as u64,
+                exit_code,
+                success: output.error.is_none(),
+                stdout: truncate(output.output.as_bytes()),
+                stderr: output.error.map(|e| e + "\n").unwrap_or_default(),
+                error: None,
+                timed_out: output.timed_out,
+                response: output.response,
+            };
+        }
+        let result = context.run(script, timeout, running);
+        let mut run = Self {
+            event: context.event.clone(),
+            script: script.to_path_buf(),
+            started_at,
+            duration_ms: started.elapsed().as_millis() as u64,
+            exit_code: None,
+            success: false,
+            stdout: String::new(),
+            stderr: String::new(),
+            error: None,
+            timed_out: false,
+            response: None,
+        };
+        match result {
+            Ok(HookOutput { output, timed_out }) => {
+                run.exit_code = output.status.code();
+                run.success = output.status.success() && !timed_out;
+                run.stdout = truncate(&output.stdout);
+                run.stderr = truncate(&output.stderr);
+                run.timed_out = timed_out;
+                if run.success {
+                    run.response = HookResponse::parse(&String::from_utf8_lossy(&output.stdout));
+                }
+            }
+            Err(e) => run.error = Some(e.to_string()),
+        }
+        run
+    }
+
+    /// A one-line explanation of a failed run, for notifications.
+    pub fn failure(&self) -> Option<String> {
+        if self.success {
+            return None;
+        }
+        let reason = match (&self.error, self.exit_code) {
+            (Some(error), _) => format!("could not be started: {}", error),
+            _ if self.timed_out => format!("timed out after {} ms", self.duration_ms),
+            (None, Some(code)) => format!("exited with status {}", code),
+            (None, None) => "was killed by a signal".to_string(),
+        };
+        let detail = self.stderr.lines().last().unwrap_or_default();
+        Some(if detail.is_empty() {
+            format!("{} {}", self.event, reason)
+        } else {
+            format!("{} {}: {}", self.event, reason, detail)
+        })
+    }
+}
+
+fn truncate(output: &[u8]) -> String {
+    let text = String::from_utf8_lossy(output);
+    if text.len() <= MAX_OUTPUT_BYTES {
+        return text.into_owned();
+    }
+    let mut end = MAX_OUTPUT_BYTES;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}\n[truncated]", &text[..end])
+}
+
+/// Hook runs as JSON lines, newest last, in `CACHE_DIR/hooks.log`.
+pub struct HookLog {
+    path: PathBuf,
+}
+
+impl Default for HookLog {
+    fn default() -> Self {
+        Self::new(CACHE_DIR.join("hooks.log"))
+    }
+}
+
+impl HookLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    fn rotated(&self) -> PathBuf {
+        self.path.with_extension("log.1")
+    }
+
+    pub fn append(&self, run: &HookRun) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let size = std::fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0);
+        if size > MAX_LOG_BYTES {
+            std::fs::rename(&self.path, self.rotated())?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        let mut line = serde_json::to_vec(run)?;
+        line.push(b'\n');
+        file.write_all(&line)?;
+        Ok(())
+    }
+
+    /// The most recent runs, newest first.
+    pub fn recent(&self, limit: usize) -> Result<Vec<HookRun>> {
+        let mut runs = Vec::new();
+        for path in [self.rotated(), self.path.clone()] {
+            let file = match File::open(&path) {
+                Ok(file) => file,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            for line in BufReader::new(file).lines() {
+                // Skip lines cut short by a crash rather than failing the list.
+                if let Ok(run) = serde_json::from_str(&line?) {
+                    runs.push(run);
+                }
+            }
+        }
+        runs.reverse();
+        runs.truncate(limit);
+        Ok(runs)
+    }
+}
+
+// Hooks waiting for the worker beyond this many are dropped.
+const QUEUE_SIZE: usize = 16;
+// Detached hooks running at once beyond this many are dropped.
+const MAX_DETACHED: usize = 4;
+
+struct Job {
+    script: PathBuf,
+    context: HookContext,
+    timeout: Option<Duration>,
+    reply: Option<Sender<HookRun>>,
+}
+
+/// A hook handed to the executor, whose outcome can be waited for.
+pub struct PendingHook {
+    receiver: Option<Receiver<HookRun>>,
+}
+
+impl PendingHook {
+    /// Blocks until the hook has run. Returns `None` for detached hooks,
+    /// which are never waited for, and for hooks dropped at shutdown.
+    pub fn wait(self) -> Option<HookRun> {
+        self.receiver?.recv().ok()
+    }
+}
+
+struct Shared {
+    log: HookLog,
+    running: RunningHooks,
+    detached: AtomicUsize,
+}
+
+impl Shared {
+    fn run(&self, job: Job) {
+        if self.running.is_cancelled() {
+            return;
+        }
+        let run = HookRun::record(&job.script, &job.context, job.timeout, &self.running);
+        if let Err(e) = self.log.append(&run) {
+            eprintln!("mytt: failed to write hook log: {}", e);
+        }
+        if let Some(failure) = run.failure() {
+            notify_on_error!(failure);
+        }
+        if let Some(message) = run.response.as_ref().and_then(|r| r.notify.as_ref()) {
+            notify!(message);
+        }
+        if let Some(reply) = job.reply {
+            let _ = reply.send(run);
+        }
+    }
+}
+
+/// Runs hook scripts off the calling thread. Serialised hooks go through a
+/// bounded queue to a single worker, detached ones get a thread each.
+pub struct CommandExecutor {
+    sender: Mutex<Option<SyncSender<Job>>>,
+    worker: Mutex<Option<JoinHandle<()>>>,
+    shared: Arc<Shared>,
+}
+
+impl CommandExecutor {
+    pub fn new() -> Self {
+        Self::with_log(HookLog::default())
+    }
+
+    pub fn with_log(log: HookLog) -> Self {
+        let (sender, receiver) = sync_channel::<Job>(QUEUE_SIZE);
+        let shared = Arc::new(Shared {
+            log,
+            running: RunningHooks::default(),
+            detached: AtomicUsize::new(0),
+        });
+
+        let worker = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || {
+                for job in receiver {
+                    shared.run(job);
+                }
+            })
+        };
+
+        Self {
+            sender: Mutex::new(Some(sender)),
+            worker: Mutex::new(Some(worker)),
+            shared,
+        }
+    }
+
+    pub fn execute(
+        &self,
+        script_path: PathBuf,
+        context: HookContext,
+        settings: HookSettings,
+    ) -> Result<PendingHook> {
+        let detached = settings.detached.unwrap_or(false);
+        let (reply, receiver) = if detached {
+            (None, None)
+        } else {
+            let (reply, receiver) = channel();
+            (Some(reply), Some(receiver))
+        };
+        let job = Job {
+            script: script_path,
+            context,
+            timeout: settings.timeout.map(Duration::from_secs),
+            reply,
+        };
+
+        if detached {
+            if self.shared.detached.fetch_add(1, Ordering::SeqCst) >= MAX_DETACHED {
+                self.shared.detached.fetch_sub(1, Ordering::SeqCst);
+                return Err(MyttError::Script(format!(
+                    "too many detached hooks running, skipped {}",
+                    job.context.event
+                )));
+            }
+            let shared = Arc::clone(&self.shared);
+            thread::spawn(move || {
+                shared.run(job);
+                shared.detached.fetch_sub(1, Ordering::SeqCst);
+            });
+            return Ok(PendingHook { receiver });
+        }
+
+        let stopped = || MyttError::Script("hook worker has stopped".to_string());
+        let sender = self.sender.lock()?;
+        let sender = sender.as_ref().ok_or_else(stopped)?;
+        sender.try_send(job).map_err(|e| match e {
+            TrySendError::Full(job) => MyttError::Script(format!(
+                "too many hooks queued, skipped {}",
+                job.context.event
+            )),
+            TrySendError::Disconnected(_) => stopped(),
+        })?;
+        Ok(PendingHook { receiver })
+    }
+
+    /// Kills the hooks that are running right now; queued ones still run.
+    pub fn cancel_running(&self) {
+        self.shared.running.kill_all();
+    }
+
+    /// Blocks until every queued and running script has finished.
+    pub fn wait(&self) {
+        self.shutdown(None);
+    }
+
+    /// Stops accepting hooks and waits for the ones already accepted. After
+    /// `grace`, whatever is still running is killed and the rest of the
+    /// queue is dropped.
+    pub fn shutdown(&self, grace: Option<Duration>) {
+        if let Ok(mut sender) = self.sender.lock() {
+            sender.take();
+        }
+        let worker = self.worker.lock().ok().and_then(|mut worker| worker.take());
+
+        let deadline = grace.map(|grace| Instant::now() + grace);
+        loop {
+            let idle = worker.as_ref().is_none_or(|w| w.is_finished())
+                && self.shared.detached.load(Ordering::SeqCst) == 0;
+            if idle {
+                break;
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                self.shared.running.cancel();
+                break;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+        if let Some(worker) = worker {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Waits for `pending` hooks in order and merges what they handed back.
+pub fn responses(pending: Vec<PendingHook>) -> HookResponse {
+    let mut merged = HookResponse::default();
+    for response in pending.into_iter().filter_map(|hook| hook.wait()?.response) {
+        merged.merge(response);
+    }
+    merged
+}
+
+/// A script found for an event, as shown by `list_hooks`.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ScriptInfo {
+    pub path: PathBuf,
+    /// Whether the script can run: executable, or a `.rhai` script.
+    pub executable: bool,
+    /// `false` when the script is listed under `disabled` in the config.
+    pub enabled: bool,
+    pub last_run: Option<HookRun>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct HookInfo {
+    pub event: String,
+    pub required: bool,
+    pub scripts: Vec<ScriptInfo>,
+}
+
+/// Every hook event with the scripts found for it in `scripts_dir` and in the
+/// directories for each kind of session.
+pub fn inventory(scripts_dir: &Path, config: &Config, log: &HookLog) -> Result<Vec<HookInfo>> {
+    let runs = log.recent(usize::MAX)?;
+    let dirs = config::script_dirs(scripts_dir, &config.session_types());
+    let hooks = EVENTS
+        .iter()
+        .map(|event| {
+            let disabled = config.hook(event).disabled.unwrap_or_default();
+            let scripts = dirs
+                .iter()
+                .flat_map(|dir| config::script_candidates(dir, event))
+                .map(|path| ScriptInfo {
+                    executable: config::is_executable(&path) || scripting::is_embedded(&path),
+                    enabled: !config::is_disabled(&path, &disabled),
+                    last_run: runs.iter().find(|run| run.script == path).cloned(),
+                    path,
+                })
+                .collect();
+            HookInfo {
+                event: event.to_string(),
+                required: config.is_required_hook(event),
+                scripts,
+            }
+        })
+        .collect();
+    Ok(hooks)
+}
@@ -0,0 +1,212 @@
+//! Notices when the user walks away from a running work session.
+//!
+//! A background thread polls how long the keyboard and mouse have been left
+//! alone. Past `idle_threshold` minutes the session is paused with the time
+//! away taken off the clock, and once the user is back the app asks what that
+//! time was: work to keep, time to discard, or a break to log on its own.
+//!
+//! The idle time comes from the X11 screensaver extension, from logind's
+//! `IdleHint` where there is no X server, and from CoreGraphics on macOS.
+use crate::config;
+use crate::error::Result;
+use crate::{app_handle, notify_on_error, pause_for_idle, return_from_idle, AppState};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// What to do with the time away from a work session.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum IdleAction {
+    /// Count it as part of the session after all.
+    Keep,
+    /// Leave it off the session.
+    Discard,
+    /// Leave it off the session and log it as an entry of its own.
+    Reassign,
+}
+
+/// Time away from a work session that has not been settled yet.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct IdlePeriod {
+    pub started_at: DateTime<Local>,
+    /// When input was seen again; `None` while the user is still away.
+    pub ended_at: Option<DateTime<Local>>,
+}
+
+impl IdlePeriod {
+    /// A period that began `idle` before now.
+    pub fn since(idle: Duration) -> Self {
+        Self {
+            started_at: Local::now() - chrono::Duration::from_std(idle).unwrap_or_default(),
+            ended_at: None,
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.ended_at.is_some()
+    }
+
+    /// How long the user was away, counting up to now if they still are.
+    pub fn duration(&self) -> Duration {
+        let ended_at = self.ended_at.unwrap_or_else(Local::now);
+        (ended_at - self.started_at).to_std().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Change {
+    /// The user has been away this long; pause the session.
+    Left(Duration),
+    /// The user is back, and last touched the keyboard or mouse this long ago.
+    Returned(Duration),
+}
+
+/// Decides what a poll means. `working` is whether a work session is
+/// running, `away` whether the user already counts as gone.
+pub(crate) fn change(
+    working: bool,
+    away: bool,
+    idle: Duration,
+    threshold: Duration,
+) -> Option<Change> {
+    match (away, idle >= threshold) {
+        (false, true) if working => Some(Change::Left(idle)),
+        (true, false) => Some(Change::Returned(idle)),
+        _ => None,
+    }
+}
+
+pub fn spawn() {
+    thread::spawn(|| loop {
+        thread::sleep(POLL_INTERVAL);
+        let Some(threshold) = config::current().idle_threshold() else {
+            continue;
+        };
+        let Some(idle) = idle_time() else {
+            continue;
+        };
+        if let Err(e) = poll(idle, threshold) {
+            notify_on_error!(e);
+        }
+    });
+}
+
+fn poll(idle: Duration, threshold: Duration) -> Result<()> {
+    let handle = app_handle()?;
+    let (working, away) = {
+        let state = handle.state::<Mutex<AppState>>();
+        let state = state.lock()?;
+        let working = state.session.is_working();
+        let away = state.idle.as_ref().is_some_and(|period| !period.is_over());
+        (working, away)
+    };
+    match change(working, away, idle, threshold) {
+        Some(Change::Left(idle)) => pause_for_idle(idle),
+        Some(Change::Returned(idle)) => return_from_idle(idle),
+        None => Ok(()),
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub use linux::idle_time;
+
+#[cfg(target_os = "macos")]
+pub use macos::idle_time;
+
+/// Idle detection is not available here.
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn idle_time() -> Option<Duration> {
+    None
+}
+
+/// Parses `loginctl show-session -p IdleHint -p IdleSinceHint` output.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub(crate) fn parse_logind_idle(reply: &str, now: SystemTime) -> Option<Duration> {
+    let property = |name: &str| {
+        reply
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix('='))
+    };
+    if property("IdleHint")? != "yes" {
+        return Some(Duration::ZERO);
+    }
+    let since = UNIX_EPOCH + Duration::from_micros(property("IdleSinceHint")?.parse().ok()?);
+    Some(now.duration_since(since).unwrap_or_default())
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::parse_logind_idle;
+    use std::env;
+    use std::process::Command;
+    use std::time::{Duration, SystemTime};
+
+    /// How long the keyboard and mouse have been left alone, if the session
+    /// can tell.
+    pub fn idle_time() -> Option<Duration> {
+        let result = if env::var_os("WAYLAND_DISPLAY").is_none() && env::var_os("DISPLAY").is_some()
+        {
+            x11()
+        } else {
+            logind()
+        };
+        // Polled every few seconds, so a session without support stays quiet.
+        result.ok().flatten()
+    }
+
+    fn x11() -> anyhow::Result<Option<Duration>> {
+        use x11rb::connection::Connection;
+        use x11rb::protocol::screensaver::ConnectionExt;
+
+        let (conn, screen) = x11rb::connect(None)?;
+        let root = conn.setup().roots[screen].root;
+        let info = conn.screensaver_query_info(root)?.reply()?;
+        Ok(Some(Duration::from_millis(info.ms_since_user_input.into())))
+    }
+
+    fn logind() -> anyhow::Result<Option<Duration>> {
+        let session = env::var("XDG_SESSION_ID").unwrap_or_else(|_| "auto".to_string());
+        let output = Command::new("loginctl")
+            .args([
+                "show-session",
+                &session,
+                "-p",
+                "IdleHint",
+                "-p",
+                "IdleSinceHint",
+            ])
+            .output()?;
+        if !output.status.success() {
+            anyhow::bail!("loginctl exited with {}", output.status);
+        }
+        let reply = String::from_utf8_lossy(&output.stdout);
+        Ok(parse_logind_idle(&reply, SystemTime::now()))
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use std::time::Duration;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGEventSourceSecondsSinceLastEventType(state: i32, event_type: u32) -> f64;
+    }
+
+    const COMBINED_SESSION_STATE: i32 = 0;
+    const ANY_INPUT_EVENT: u32 = u32::MAX;
+
+    /// How long the keyboard and mouse have been left alone.
+    pub fn idle_time() -> Option<Duration> {
+        let seconds = unsafe {
+            CGEventSourceSecondsSinceLastEventType(COMBINED_SESSION_STATE, ANY_INPUT_EVENT)
+        };
+        Duration::try_from_secs_f64(seconds).ok()
+    }
+}
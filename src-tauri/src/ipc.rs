@@ -0,0 +1,267 @@
+//! Control socket in `RUNTIME_DIR` that lets other processes (the CLI, editor
+//! plugins, status bars) drive and observe the running app.
+//!
+//! The protocol is newline-delimited JSON. Each request line gets exactly one
+//! response line, except `subscribe`, after which the server additionally
+//! pushes a `state_changed` line whenever the timer changes:
+//!
+//! ```text
+//! > {"cmd":"start"}
+//! < {"status":{"session_type":"work","state":"running","elapsed_ms":0,"remaining_ms":1500000}}
+//! > {"cmd":"get_time"}
+//! < {"time":"00:00:03"}
+//! > {"cmd":"subscribe"}
+//! < "subscribed"
+//! < {"state_changed":{"session_type":"work","state":"paused",...}}
+//! ```
+use crate::adaptive::{FocusRating, FocusStats, Suggestion};
+use crate::config::RUNTIME_DIR;
+use crate::error::MyttError;
+use crate::idle::IdleAction;
+use crate::{
+    current_status, lap_timer, on_log, on_pause, on_resume, on_start, on_stop, rate_session,
+    resolve_idle, suggest_durations, SessionType, Status,
+};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// How long a subscriber that stopped reading may hold up a state change
+/// before it is dropped.
+const SUBSCRIBER_TIMEOUT: Duration = Duration::from_millis(500);
+
+type Subscriber = Arc<Mutex<UnixStream>>;
+
+lazy_static! {
+    static ref SUBSCRIBERS: Mutex<Vec<Subscriber>> = Mutex::new(Vec::new());
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum Request {
+    Start,
+    Pause,
+    Resume,
+    Stop,
+    Log,
+    GetTime,
+    Status,
+    Subscribe,
+    ResolveIdle {
+        action: IdleAction,
+        session_type: Option<SessionType>,
+    },
+    Lap {
+        label: Option<String>,
+    },
+    Suggest {
+        rating: Option<FocusRating>,
+    },
+    Rate {
+        rating: FocusRating,
+        note: Option<String>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Response {
+    Status(Status),
+    Time(String),
+    Suggestion(Suggestion),
+    FocusStats(FocusStats),
+    Subscribed,
+    StateChanged(Status),
+    Error(String),
+}
+
+pub fn socket_path() -> PathBuf {
+    RUNTIME_DIR.join("mytt.sock")
+}
+
+/// Runs a request against the app's managed state.
+pub fn dispatch(request: Request) -> Response {
+    if let Request::Suggest { rating } = request {
+        return match suggest_durations(rating) {
+            Ok(suggestion) => Response::Suggestion(suggestion),
+            Err(e) => Response::Error(e.to_string()),
+        };
+    }
+    if let Request::Rate { rating, note } = request {
+        return match rate_session(rating, note) {
+            Ok(stats) => Response::FocusStats(stats),
+            Err(e) => Response::Error(e.to_string()),
+        };
+    }
+    let result = match request {
+        Request::Start => on_start(None).map(|_| ()),
+        Request::Pause => on_pause(),
+        Request::Resume => on_resume(),
+        Request::Stop => on_stop(),
+        Request::Log => on_log(None),
+        Request::ResolveIdle {
+            action,
+            ref session_type,
+        } => resolve_idle(action, session_type.clone()),
+        Request::Lap { ref label } => lap_timer(label.clone()).map(|_| ()),
+        Request::GetTime
+        | Request::Status
+        | Request::Subscribe
+        | Request::Suggest { .. }
+        | Request::Rate { .. } => Ok(()),
+    };
+    let result = result.and_then(|_| current_status());
+    match (request, result) {
+        (_, Err(e)) => Response::Error(e.to_string()),
+        (Request::GetTime, Ok(status)) => {
+            Response::Time(crate::format_millis(status.elapsed_ms as u128))
+        }
+        (_, Ok(status)) => Response::Status(status),
+    }
+}
+
+/// Sends `status` to every subscribed client, dropping the ones that hung up
+/// or stopped reading.
+///
+/// The list is only locked to copy it and to drop clients, so a slow client
+/// keeps neither new subscribers nor other publishers waiting.
+pub fn publish(status: &Status) {
+    let Ok(line) = encode(&Response::StateChanged(status.clone())) else {
+        return;
+    };
+    let Ok(subscribers) = SUBSCRIBERS.lock().map(|subscribers| subscribers.clone()) else {
+        return;
+    };
+    let gone: Vec<Subscriber> = subscribers
+        .into_iter()
+        .filter(|subscriber| match subscriber.lock() {
+            Ok(mut stream) => stream.write_all(line.as_bytes()).is_err(),
+            Err(_) => true,
+        })
+        .collect();
+    if gone.is_empty() {
+        return;
+    }
+    if let Ok(mut subscribers) = SUBSCRIBERS.lock() {
+        subscribers.retain(|subscriber| !gone.iter().any(|gone| Arc::ptr_eq(subscriber, gone)));
+    }
+}
+
+/// Binds the socket at `path` and serves each connection on its own thread.
+///
+/// A socket left behind by a crashed instance is replaced; a live one means
+/// another instance already owns the timer and is reported as an error.
+pub fn serve<F>(path: PathBuf, handler: F) -> anyhow::Result<()>
+where
+    F: Fn(Request) -> Response + Send + Sync + 'static,
+{
+    if path.exists() {
+        if UnixStream::connect(&path).is_ok() {
+            anyhow::bail!("another instance is listening on {}", path.display());
+        }
+        std::fs::remove_file(&path)?;
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let listener = UnixListener::bind(&path)?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+
+    let handler = Arc::new(handler);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let handler = Arc::clone(&handler);
+            thread::spawn(move || {
+                if let Err(e) = handle_connection(stream, handler.as_ref()) {
+                    eprintln!("mytt: control socket: {}", e);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+fn handle_connection<F>(stream: UnixStream, handler: &F) -> anyhow::Result<()>
+where
+    F: Fn(Request) -> Response,
+{
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let request = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                writer.write_all(encode(&Response::Error(e.to_string()))?.as_bytes())?;
+                continue;
+            }
+        };
+        if request == Request::Subscribe {
+            let subscriber = writer.try_clone()?;
+            subscriber.set_write_timeout(Some(SUBSCRIBER_TIMEOUT))?;
+            SUBSCRIBERS
+                .lock()
+                .map_err(MyttError::from)?
+                .push(Arc::new(Mutex::new(subscriber)));
+            writer.write_all(encode(&Response::Subscribed)?.as_bytes())?;
+            continue;
+        }
+        writer.write_all(encode(&handler(request))?.as_bytes())?;
+    }
+    Ok(())
+}
+
+fn encode(response: &Response) -> serde_json::Result<String> {
+    let mut line = serde_json::to_string(response)?;
+    line.push('\n');
+    Ok(line)
+}
+
+/// Connection to the control socket of a running app.
+pub struct Client {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+}
+
+impl Client {
+    /// Returns `Ok(None)` when no app is listening at `path`.
+    pub fn connect(path: &Path) -> anyhow::Result<Option<Self>> {
+        let stream = match UnixStream::connect(path) {
+            Ok(stream) => stream,
+            Err(e) if matches!(e.kind(), ErrorKind::NotFound | ErrorKind::ConnectionRefused) => {
+                return Ok(None)
+            }
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Some(Self {
+            writer: stream.try_clone()?,
+            reader: BufReader::new(stream),
+        }))
+    }
+
+    pub fn send(&mut self, request: Request) -> anyhow::Result<Response> {
+        let mut line = serde_json::to_string(&request)?;
+        line.push('\n');
+        self.writer.write_all(line.as_bytes())?;
+        self.recv()?
+            .ok_or_else(|| anyhow::anyhow!("control socket closed the connection"))
+    }
+
+    /// Reads the next message from the server; `None` once it hangs up.
+    pub fn recv(&mut self) -> anyhow::Result<Option<Response>> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&line)?))
+    }
+}
@@ -0,0 +1,269 @@
+use crate::adaptive::FocusRating;
+use crate::config::DATA_DIR;
+use crate::error::{MyttError, Result};
+use crate::SessionType;
+use chrono::{DateTime, Local};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+// Each entry is applied once, in order, and recorded in `PRAGMA user_version`.
+// Never edit a released migration; append a new one instead.
+const MIGRATIONS: &[&str] = &[
+    // 1: initial schema
+    "CREATE TABLE sessions (
+        id           INTEGER PRIMARY KEY AUTOINCREMENT,
+        started_at   TEXT NOT NULL,
+        ended_at     TEXT NOT NULL,
+        session_type TEXT NOT NULL,
+        paused_ms    INTEGER NOT NULL DEFAULT 0,
+        app_name     TEXT,
+        focus_rating TEXT,
+        note         TEXT
+    );
+    CREATE INDEX sessions_started_at ON sessions (started_at);",
+    // 2: tags set by hooks, as a JSON array
+    "ALTER TABLE sessions ADD COLUMN tags TEXT NOT NULL DEFAULT '[]';",
+    // 3: time the machine was suspended during the session
+    "ALTER TABLE sessions ADD COLUMN suspended_ms INTEGER NOT NULL DEFAULT 0;",
+    // 4: whether the session was a break, which custom segments decide for
+    // themselves, and the times as Unix seconds, so ordering and comparisons
+    // do not depend on how the text spells the offset
+    "ALTER TABLE sessions ADD COLUMN is_break INTEGER NOT NULL DEFAULT 0;
+    UPDATE sessions SET is_break = 1 WHERE session_type IN ('shortBreak', 'longBreak');
+    ALTER TABLE sessions ADD COLUMN started_epoch INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE sessions ADD COLUMN ended_epoch INTEGER NOT NULL DEFAULT 0;
+    UPDATE sessions SET
+        started_epoch = CAST(strftime('%s', started_at) AS INTEGER),
+        ended_epoch = CAST(strftime('%s', ended_at) AS INTEGER);
+    CREATE INDEX sessions_ended_epoch ON sessions (ended_epoch);",
+];
+
+// Sessions that were not breaks, which are the ones rated for focus.
+const WORK: &str = "NOT is_break";
+
+/// A session as it is about to be written to the journal.
+#[derive(Debug, Clone)]
+pub struct NewJournalEntry {
+    pub started_at: DateTime<Local>,
+    pub ended_at: DateTime<Local>,
+    pub session_type: SessionType,
+    /// Whether the segment was a break, as its sequence has it.
+    pub is_break: bool,
+    pub paused_ms: u64,
+    /// Time the machine spent asleep during the session. It is part of
+    /// `paused_ms`, unless `suspend_policy` is `count`, which adds it to the
+    /// time worked instead.
+    pub suspended_ms: u64,
+    pub app_name: Option<String>,
+    pub focus_rating: Option<String>,
+    pub note: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// A session read back from the journal.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JournalEntry {
+    pub id: i64,
+    pub started_at: DateTime<Local>,
+    pub ended_at: DateTime<Local>,
+    pub session_type: SessionType,
+    pub is_break: bool,
+    pub paused_ms: u64,
+    pub suspended_ms: u64,
+    pub app_name: Option<String>,
+    pub focus_rating: Option<String>,
+    pub note: Option<String>,
+    pub tags: Vec<String>,
+}
+
+impl JournalEntry {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let session_type: String = row.get("session_type")?;
+        let tags: String = row.get("tags")?;
+        Ok(Self {
+            id: row.get("id")?,
+            started_at: row.get("started_at")?,
+            ended_at: row.get("ended_at")?,
+            session_type: session_type.parse().unwrap_or_default(),
+            is_break: row.get("is_break")?,
+            paused_ms: row.get::<_, i64>("paused_ms")? as u64,
+            suspended_ms: row.get::<_, i64>("suspended_ms")? as u64,
+            app_name: row.get("app_name")?,
+            focus_rating: row.get("focus_rating")?,
+            note: row.get("note")?,
+            tags: serde_json::from_str(&tags).unwrap_or_default(),
+        })
+    }
+}
+
+/// Persistent record of completed sessions, stored as SQLite in `DATA_DIR`.
+pub struct Journal {
+    conn: Connection,
+}
+
+impl Journal {
+    pub fn open_default() -> Result<Self> {
+        std::fs::create_dir_all(DATA_DIR.as_path())?;
+        Self::open(DATA_DIR.join("journal.sqlite3"))
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    /// Used when the journal file cannot be opened, so the app keeps working
+    /// for the rest of the run.
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(mut conn: Connection) -> Result<Self> {
+        migrate(&mut conn)?;
+        Ok(Self { conn })
+    }
+
+    pub fn insert(&self, entry: &NewJournalEntry) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO sessions
+                (started_at, ended_at, session_type, paused_ms, app_name, focus_rating, note, tags,
+                 suspended_ms, is_break, started_epoch, ended_epoch)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                entry.started_at,
+                entry.ended_at,
+                entry.session_type.as_str(),
+                entry.paused_ms as i64,
+                entry.app_name,
+                entry.focus_rating,
+                entry.note,
+                serde_json::to_string(&entry.tags)?,
+                entry.suspended_ms as i64,
+                entry.is_break,
+                entry.started_at.timestamp(),
+                entry.ended_at.timestamp(),
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Most recent sessions first.
+    pub fn list(&self, limit: u32, offset: u32) -> Result<Vec<JournalEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT * FROM sessions ORDER BY started_epoch DESC, id DESC LIMIT ?1 OFFSET ?2",
+        )?;
+        let entries = stmt
+            .query_map(params![limit, offset], JournalEntry::from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(entries)
+    }
+
+    /// Focus ratings of the latest rated work sessions, newest first.
+    pub fn recent_ratings(&self, limit: u32) -> Result<Vec<FocusRating>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT focus_rating FROM sessions
+             WHERE focus_rating IS NOT NULL AND {WORK}
+             ORDER BY ended_epoch DESC, id DESC LIMIT ?1"
+        ))?;
+        let ratings = stmt
+            .query_map(params![limit], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(ratings.iter().filter_map(|r| r.parse().ok()).collect())
+    }
+
+    /// How many of the latest rated work sessions in a row were rated `flow`.
+    pub fn flow_streak(&self) -> Result<u32> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT focus_rating FROM sessions
+             WHERE focus_rating IS NOT NULL AND {WORK}
+             ORDER BY ended_epoch DESC, id DESC"
+        ))?;
+        let mut rows = stmt.query([])?;
+        let mut streak = 0;
+        while let Some(row) = rows.next()? {
+            match row.get::<_, String>(0)?.parse() {
+                Ok(FocusRating::Flow) => streak += 1,
+                Ok(_) => break,
+                // Ratings this build does not know are skipped, as in `recent_ratings`.
+                Err(_) => {}
+            }
+        }
+        Ok(streak)
+    }
+
+    /// Work sessions rated `flow` that ended at or after `since`.
+    pub fn flow_sessions_since(&self, since: DateTime<Local>) -> Result<u32> {
+        let count = self.conn.query_row(
+            &format!(
+                "SELECT COUNT(*) FROM sessions
+                 WHERE focus_rating = 'flow' AND {WORK} AND ended_epoch >= ?1"
+            ),
+            params![since.timestamp()],
+            |row| row.get(0),
+        )?;
+        Ok(count)
+    }
+
+    /// Rates the latest logged work session, replacing any rating it had,
+    /// and sets its note if `note` is given. Returns its id, or `None` when
+    /// no work session has been logged yet.
+    pub fn rate_latest(&self, rating: FocusRating, note: Option<&str>) -> Result<Option<i64>> {
+        let id = self
+            .conn
+            .query_row(
+                &format!(
+                    "SELECT id FROM sessions WHERE {WORK}
+                     ORDER BY ended_epoch DESC, id DESC LIMIT 1"
+                ),
+                [],
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()?;
+        if let Some(id) = id {
+            self.conn.execute(
+                "UPDATE sessions SET focus_rating = ?1, note = COALESCE(?2, note) WHERE id = ?3",
+                params![rating.as_str(), note, id],
+            )?;
+        }
+        Ok(id)
+    }
+
+    pub fn get(&self, id: i64) -> Result<Option<JournalEntry>> {
+        let entry = self
+            .conn
+            .query_row(
+                "SELECT * FROM sessions WHERE id = ?1",
+                params![id],
+                JournalEntry::from_row,
+            )
+            .optional()?;
+        Ok(entry)
+    }
+
+    /// Returns `false` if there was no entry with that id.
+    pub fn delete(&self, id: i64) -> Result<bool> {
+        let deleted = self
+            .conn
+            .execute("DELETE FROM sessions WHERE id = ?1", params![id])?;
+        Ok(deleted > 0)
+    }
+}
+
+fn migrate(conn: &mut Connection) -> Result<()> {
+    let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version > MIGRATIONS.len() {
+        return Err(MyttError::Journal(format!(
+            "schema version {} is newer than this build supports ({})",
+            version,
+            MIGRATIONS.len()
+        )));
+    }
+
+    let tx = conn.transaction()?;
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", i + 1)?;
+    }
+    tx.commit()?;
+    Ok(())
+}
@@ -1,43 +1,77 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod adaptive;
+mod cli;
+mod clock;
 mod config;
-use crate::config::get_script;
+mod countdown;
+mod error;
+mod frontmost;
+mod hooks;
+mod idle;
+#[cfg(unix)]
+mod ipc;
+mod journal;
+mod scripting;
+mod session;
+#[cfg(test)]
+mod tests;
+mod watcher;
+use crate::adaptive::{FocusRating, FocusStats, Suggestion};
+use crate::clock::{Clock, SystemClock};
+use crate::config::{get_scripts, SuspendPolicy};
+use crate::error::{MyttError, Result};
+use crate::frontmost::FrontmostApp;
+use crate::hooks::{CommandExecutor, HookContext, HookLog, HookResponse, HookRun, PendingHook};
+use crate::idle::{IdleAction, IdlePeriod};
+use crate::journal::{Journal, JournalEntry, NewJournalEntry};
+pub use crate::session::{Phase, Sequence, Session, SessionEvent, SessionType, Transition};
+use chrono::{DateTime, Local};
 #[cfg(target_os = "macos")]
 use cocoa::base::id;
 #[cfg(target_os = "macos")]
 use objc::{class, msg_send, sel, sel_impl};
-use std::path::PathBuf;
-use std::process::Command;
-use std::sync::mpsc::{channel, Sender};
-use std::sync::{Mutex, OnceLock};
-use std::thread;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, Instant};
-use tauri::{AppHandle, Builder, Emitter, Manager, Window, WindowEvent};
+use tauri::{AppHandle, Builder, Emitter, Manager, RunEvent, Window, WindowEvent};
+
+/// How long hooks still running at exit get to finish before they are killed.
+const HOOK_SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
 
 lazy_static::lazy_static! {
     pub static ref APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
 }
 
+fn app_handle() -> Result<&'static AppHandle> {
+    APP_HANDLE
+        .get()
+        .ok_or_else(|| MyttError::State("app is not initialised".to_string()))
+}
+
 #[macro_export]
 macro_rules! notify {
     ($($arg:tt)*) => {{
         use tauri_plugin_notification::NotificationExt;
-        let app_handle = crate::APP_HANDLE.get().unwrap();
-        app_handle
-            .notification()
-            .builder()
-            .title("mytt")
-            .body($($arg)*) // e.g. "Message"
-            .show()
-            .unwrap_or_else(|e| eprintln!("{}", e));
+        match $crate::APP_HANDLE.get() {
+            Some(app_handle) => app_handle
+                .notification()
+                .builder()
+                .title("mytt")
+                .body($($arg)*) // e.g. "Message"
+                .show()
+                .unwrap_or_else(|e| eprintln!("{}", e)),
+            // Headless (CLI) runs have no app handle to notify through.
+            None => eprintln!("mytt: {}", $($arg)*),
+        }
         }};
 }
 
 #[macro_export]
 macro_rules! notify_on_error {
     ($($arg:tt)*) => {{
-        crate::notify!(format!("Error: {}", $($arg)*));
+        $crate::notify!(format!("Error: {}", $($arg)*));
         }};
 }
 
@@ -61,259 +95,964 @@ macro_rules! nsstring_to_string {
 }
 
 pub struct Stopwatch {
+    clock: Arc<dyn Clock>,
+    /// When the current run started; `None` while paused or stopped.
     start_time: Option<Instant>,
-    paused_duration: Duration,
+    /// Elapsed time not covered by `start_time`: earlier runs and time
+    /// counted in from elsewhere. Kept apart so nothing has to rewind an
+    /// `Instant`, which cannot go back past the time the machine booted.
+    offset: Duration,
+    started_at: Option<DateTime<Local>>,
+    /// When the running stopwatch was last checked, on the monotonic clock
+    /// and the wall clock, to notice a suspend between checks.
+    checked: Option<(Instant, DateTime<Local>)>,
+    /// Time the machine slept through since `started_at`.
+    suspended: Duration,
+    laps: Vec<Lap>,
 }
 
-pub struct CommandExecutor {
-    sender: Sender<(String, String, String)>,
+/// A split taken inside a session without stopping the timer.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Lap {
+    /// What the time since the previous lap went on, e.g. "reviewing PR".
+    pub label: Option<String>,
+    /// Elapsed time of the session when the lap was taken.
+    pub elapsed_ms: u64,
+    /// Time since the previous lap, or since the start for the first one.
+    pub split_ms: u64,
+    pub taken_at: DateTime<Local>,
 }
 
-impl CommandExecutor {
-    pub fn new() -> Self {
-        let (sender, receiver) = channel();
-
-        thread::spawn(move || {
-            for (script, time, app_name) in receiver {
-                let _ = Command::new("sh")
-                    .arg("-c")
-                    .arg(&script)
-                    .arg(&time)
-                    .arg(&app_name)
-                    .output();
-            }
-        });
+/// A gap the wall clock jumped over while the monotonic clock stood still,
+/// which is what a system suspend looks like.
+const SUSPEND_THRESHOLD: Duration = Duration::from_secs(10);
+
+pub fn format_millis(millis: u128) -> String {
+    let hours = millis / 3600000;
+    let minutes = (millis % 3600000) / 60000;
+    let seconds = (millis % 60000) / 1000;
+
+    format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TimerState {
+    Running,
+    Paused,
+    Stopped,
+}
+
+impl TimerState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimerState::Running => "running",
+            TimerState::Paused => "paused",
+            TimerState::Stopped => "stopped",
+        }
+    }
+}
+
+/// Snapshot of the timer as reported to the CLI and IPC clients.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Status {
+    pub session_type: SessionType,
+    pub phase: Phase,
+    pub state: TimerState,
+    pub elapsed_ms: u64,
+    /// Always 0 for open-ended flowtime work.
+    pub remaining_ms: u64,
+    /// Flowtime work, which counts up until it is stopped.
+    #[serde(default)]
+    pub open_ended: bool,
+}
 
-        Self { sender }
+impl Status {
+    fn new(stopwatch: &Stopwatch, session: &Session) -> Self {
+        let planned = session.target_duration() as u128 * 1000;
+        let elapsed = stopwatch.elapsed();
+        let open_ended = session.is_open_ended();
+        Self {
+            session_type: session.current_session(),
+            phase: session.phase(),
+            state: stopwatch.state(),
+            elapsed_ms: elapsed as u64,
+            remaining_ms: if open_ended {
+                0
+            } else {
+                planned.saturating_sub(elapsed) as u64
+            },
+            open_ended,
+        }
     }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StopwatchSnapshot {
+    elapsed_ms: u64,
+    running: bool,
+    started_at: Option<DateTime<Local>>,
+    taken_at: DateTime<Local>,
+    #[serde(default)]
+    laps: Vec<Lap>,
+}
 
-    pub fn execute(&self, script_path: PathBuf, time: String, app_name: String) {
-        let script_path = script_path.to_str().unwrap().to_string();
-        let _ = self.sender.send((script_path, time, app_name));
+impl Default for Stopwatch {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl Stopwatch {
     pub fn new() -> Self {
+        Self::with_clock(Arc::new(SystemClock))
+    }
+
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
         Self {
+            clock,
             start_time: None,
-            paused_duration: Duration::new(0, 0),
+            offset: Duration::new(0, 0),
+            started_at: None,
+            checked: None,
+            suspended: Duration::new(0, 0),
+            laps: Vec::new(),
         }
     }
 
+    /// The clock this stopwatch reads the time from.
+    pub fn clock(&self) -> &dyn Clock {
+        self.clock.as_ref()
+    }
+
     pub fn start(&mut self) {
+        if self.started_at.is_none() {
+            self.started_at = Some(self.clock.wall());
+        }
         if self.start_time.is_none() {
-            self.start_time = Some(Instant::now() - self.paused_duration);
-            self.paused_duration = Duration::new(0, 0);
+            self.start_time = Some(self.clock.now());
         }
     }
 
     pub fn pause(&mut self) {
-        if let Some(start_time) = self.start_time {
-            self.paused_duration = Instant::now() - start_time;
-            self.start_time = None;
+        if let Some(start_time) = self.start_time.take() {
+            self.offset += self.clock.now() - start_time;
         }
     }
 
     pub fn resume(&mut self) {
         if self.start_time.is_none() {
-            self.start_time = Some(Instant::now() - self.paused_duration);
+            self.start_time = Some(self.clock.now());
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.start_time.is_some()
+    }
+
+    /// Counts `by` as if the stopwatch had been running for it.
+    pub fn add_elapsed(&mut self, by: Duration) {
+        self.offset += by;
+    }
+
+    /// Takes `by` off the elapsed time, down to zero.
+    pub fn remove_elapsed(&mut self, by: Duration) {
+        // Off the earlier runs first, then off the current one.
+        let rest = by.saturating_sub(self.offset);
+        self.offset = self.offset.saturating_sub(by);
+        if let Some(start_time) = self.start_time {
+            self.start_time = Some((start_time + rest).min(self.clock.now()));
         }
     }
 
     pub fn stop(&mut self) {
         self.start_time = None;
-        self.paused_duration = Duration::new(0, 0);
+        self.offset = Duration::new(0, 0);
+        self.started_at = None;
+        self.suspended = Duration::new(0, 0);
+        self.laps.clear();
     }
 
     pub fn elapsed(&self) -> u128 {
         if let Some(start_time) = self.start_time {
-            (self.paused_duration.as_millis() + (Instant::now() - start_time).as_millis()) as u128
+            self.offset.as_millis() + (self.clock.now() - start_time).as_millis()
         } else {
-            self.paused_duration.as_millis() as u128
+            self.offset.as_millis()
         }
     }
 
     pub fn format_time(&self) -> String {
-        let elapsed = self.elapsed();
-        let hours = elapsed / 3600000;
-        let minutes = (elapsed % 3600000) / 60000;
-        let seconds = (elapsed % 60000) / 1000;
+        format_millis(self.elapsed())
+    }
 
-        format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
+    pub fn state(&self) -> TimerState {
+        if self.is_running() {
+            TimerState::Running
+        } else if self.started_at.is_some() {
+            TimerState::Paused
+        } else {
+            TimerState::Stopped
+        }
     }
 
     pub fn reset(&mut self) {
         self.start_time = None;
-        self.paused_duration = Duration::new(0, 0);
+        self.offset = Duration::new(0, 0);
+        self.started_at = None;
+        self.suspended = Duration::new(0, 0);
+        self.laps.clear();
+    }
+
+    /// Wall-clock time of the first `start` since the last `stop`/`reset`.
+    pub fn started_at(&self) -> Option<DateTime<Local>> {
+        self.started_at
+    }
+
+    /// Captures the stopwatch so it can be restored by another process.
+    pub fn snapshot(&self) -> StopwatchSnapshot {
+        StopwatchSnapshot {
+            elapsed_ms: self.elapsed() as u64,
+            running: self.is_running(),
+            started_at: self.started_at,
+            taken_at: self.clock.wall(),
+            laps: self.laps.clone(),
+        }
+    }
+
+    /// Rebuilds a stopwatch from a snapshot, counting the time since it was
+    /// taken if the stopwatch was running.
+    pub fn restore(snapshot: &StopwatchSnapshot) -> Self {
+        Self::restore_with_clock(snapshot, Arc::new(SystemClock))
+    }
+
+    pub fn restore_with_clock(snapshot: &StopwatchSnapshot, clock: Arc<dyn Clock>) -> Self {
+        let mut elapsed = Duration::from_millis(snapshot.elapsed_ms);
+        if snapshot.running {
+            let since = (clock.wall() - snapshot.taken_at).num_milliseconds().max(0);
+            elapsed += Duration::from_millis(since as u64);
+        }
+        Self {
+            start_time: snapshot.running.then(|| clock.now()),
+            offset: elapsed,
+            clock,
+            started_at: snapshot.started_at,
+            checked: None,
+            suspended: Duration::new(0, 0),
+            laps: snapshot.laps.clone(),
+        }
+    }
+
+    /// Records a split at the current elapsed time. Returns `None` if no
+    /// session has been started.
+    pub fn lap(&mut self, label: Option<String>) -> Option<Lap> {
+        self.started_at?;
+        let elapsed_ms = self.elapsed() as u64;
+        let previous = self.laps.last().map_or(0, |lap| lap.elapsed_ms);
+        let lap = Lap {
+            label: label.filter(|label| !label.trim().is_empty()),
+            elapsed_ms,
+            split_ms: elapsed_ms.saturating_sub(previous),
+            taken_at: self.clock.wall(),
+        };
+        self.laps.push(lap.clone());
+        Some(lap)
+    }
+
+    /// Starts, pauses or stops the stopwatch to match `phase`.
+    pub fn follow(&mut self, phase: &Phase) {
+        match phase {
+            Phase::Idle => self.stop(),
+            Phase::Running(_) => self.start(),
+            Phase::Paused(_) | Phase::AwaitingRating => self.pause(),
+        }
+    }
+
+    /// The laps taken since `started_at`, oldest first.
+    pub fn laps(&self) -> &[Lap] {
+        &self.laps
+    }
+
+    /// Returns how long the machine was suspended since the last call, if it
+    /// was. The monotonic clock stops while the machine sleeps on Linux and
+    /// macOS and the wall clock does not, so a suspend shows up as the difference.
+    pub fn detect_suspend(&mut self) -> Option<Duration> {
+        if !self.is_running() {
+            self.checked = None;
+            return None;
+        }
+        let now = (self.clock.now(), self.clock.wall());
+        let (instant, wall) = self.checked.replace(now)?;
+        let monotonic = now.0 - instant;
+        let wall = (now.1 - wall).to_std().ok()?;
+        wall.checked_sub(monotonic)
+            .filter(|gap| *gap >= SUSPEND_THRESHOLD)
+    }
+
+    /// Accounts for a suspend of `gap` found by `detect_suspend`.
+    pub fn apply_suspend(&mut self, gap: Duration, policy: SuspendPolicy) {
+        self.suspended += gap;
+        match policy {
+            SuspendPolicy::Count => self.add_elapsed(gap),
+            SuspendPolicy::Exclude => {}
+            // The clock did not move while asleep, so pausing now stops it
+            // where it was when the machine went to sleep.
+            SuspendPolicy::Pause => self.pause(),
+        }
+    }
+
+    /// Time the machine slept through since `started_at`.
+    pub fn suspended(&self) -> Duration {
+        self.suspended
+    }
+
+    /// Wall-clock time spent paused since `started_at`, in milliseconds.
+    pub fn paused_time(&self) -> u128 {
+        match self.started_at {
+            Some(started_at) => {
+                let wall = (self.clock.wall() - started_at).num_milliseconds().max(0) as u128;
+                wall.saturating_sub(self.elapsed())
+            }
+            None => 0,
+        }
     }
 }
 #[tauri::command]
-fn fetch_config() -> String {
-    let json = serde_json::to_string(&config::Config::load()).unwrap();
-    json
+fn fetch_config() -> Result<String> {
+    Ok(serde_json::to_string(&config::reload())?)
 }
 
+/// Merges a JSON object of settings into `config.yaml`.
 #[tauri::command]
-fn start_timer(stopwatch: tauri::State<Mutex<Stopwatch>>) {
-    if let Ok(mut stopwatch) = stopwatch.lock() {
-        stopwatch.start();
-    }
+fn save_config(config: String) -> Result<config::Config> {
+    let config = config::save_update(&config)?;
+    apply_config(&config)?;
+    Ok(config)
+}
+
+/// The sequences on offer, the one the session runs and the segment it is on.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct SequenceList {
+    pub active: String,
+    pub position: usize,
+    pub sequences: Vec<Sequence>,
 }
 
+/// Lists the classic cycle and the sequences defined in `config.yaml`.
 #[tauri::command]
-fn stop_timer(stopwatch: tauri::State<Mutex<Stopwatch>>) {
-    if let Ok(mut stopwatch) = stopwatch.lock() {
-        stopwatch.stop();
-    }
+fn list_sequences(state: tauri::State<Mutex<AppState>>) -> Result<SequenceList> {
+    let state = state.lock()?;
+    Ok(SequenceList {
+        active: state.session.sequence().name.clone(),
+        position: state.session.position(),
+        sequences: Sequence::all(&config::current()),
+    })
 }
 
+/// Switches to the sequence called `name` and saves the choice. A session
+/// already under way finishes in the old sequence first.
 #[tauri::command]
-fn get_time(stopwatch: tauri::State<Mutex<Stopwatch>>) -> String {
-    if let Ok(stopwatch) = stopwatch.lock() {
-        println!("{}", stopwatch.format_time());
-        return stopwatch.format_time();
-    }
-    "Error".to_string()
+fn select_sequence(name: String) -> Result<config::Config> {
+    let config = config::select_sequence(&name)?;
+    apply_config(&config)?;
+    Ok(config)
 }
 
+/// Checks `text`, or the config file on disk when omitted, without applying it.
 #[tauri::command]
-fn pause_timer(stopwatch: tauri::State<Mutex<Stopwatch>>) {
-    let mut stopwatch = stopwatch.lock().unwrap();
-    stopwatch.pause();
+fn validate_config(text: Option<String>) -> Result<config::ConfigCheck> {
+    let text = match text {
+        Some(text) => text,
+        None => std::fs::read_to_string(config::Config::file()).unwrap_or_default(),
+    };
+    Ok(config::ConfigCheck::new(&text))
 }
 
+/// Starts the stopwatch without running hooks.
 #[tauri::command]
-fn resume_timer(stopwatch: tauri::State<Mutex<Stopwatch>>) {
-    let mut stopwatch = stopwatch.lock().unwrap();
-    stopwatch.resume();
+fn start_timer() -> Result<()> {
+    step(Transition::Start).map(|_| ())
 }
 
 #[tauri::command]
-fn reset_timer(stopwatch: tauri::State<Mutex<Stopwatch>>) {
-    let mut stopwatch = stopwatch.lock().unwrap();
-    stopwatch.reset();
+fn stop_timer() -> Result<()> {
+    step(Transition::Stop).map(|_| ())
 }
 
 #[tauri::command]
-fn run_script(path: PathBuf) -> () {
-    let handle = APP_HANDLE.get().unwrap();
-    let stopwatch = handle.state::<Mutex<Stopwatch>>();
-    let command_executor = handle.state::<CommandExecutor>();
-    let front_app_name = get_frontmost_application().unwrap_or_else(|| "Unknown".to_string());
-    let stopwatch = stopwatch.lock().unwrap();
-    let time = stopwatch.format_time();
-    command_executor.execute(path, time, front_app_name);
+fn get_time(stopwatch: tauri::State<Mutex<Stopwatch>>) -> Result<String> {
+    Ok(stopwatch.lock()?.format_time())
 }
 
 #[tauri::command]
-fn on_log() -> Result<(), String> {
-    if let Some(path) = get_script("on_log") {
-        run_script(path);
-    }
-    on_stop();
-    Ok(())
+fn pause_timer() -> Result<()> {
+    step(Transition::Pause).map(|_| ())
+}
+
+#[tauri::command]
+fn resume_timer() -> Result<()> {
+    step(Transition::Resume).map(|_| ())
 }
 
+/// Same as `stop_timer`; the session goes back to idle either way.
 #[tauri::command]
-fn on_start() {
-    if let Some(path) = get_script("on_start") {
-        run_script(path);
+fn reset_timer() -> Result<()> {
+    step(Transition::Stop).map(|_| ())
+}
+
+/// Splits the running session at the current time under `label`.
+#[tauri::command]
+fn lap_timer(label: Option<String>) -> Result<Lap> {
+    let handle = app_handle()?;
+    let lap = handle
+        .state::<Mutex<Stopwatch>>()
+        .lock()?
+        .lap(label)
+        .ok_or_else(|| MyttError::State("there is no session to split".to_string()))?;
+    handle.emit("lap", &lap)?;
+    Ok(lap)
+}
+
+#[tauri::command]
+fn get_laps(stopwatch: tauri::State<Mutex<Stopwatch>>) -> Result<Vec<Lap>> {
+    Ok(stopwatch.lock()?.laps().to_vec())
+}
+
+/// Queues the scripts for `event`, if there are any.
+fn run_hook(event: &str) -> Result<Vec<PendingHook>> {
+    queue_hook(event, None)
+}
+
+fn queue_hook(event: &str, transition: Option<Transition>) -> Result<Vec<PendingHook>> {
+    queue_hook_with(event, |context| HookContext {
+        transition,
+        ..context
+    })
+}
+
+/// Like `queue_hook`, with `extend` adding to the context the scripts get.
+fn queue_hook_with<F>(event: &str, extend: F) -> Result<Vec<PendingHook>>
+where
+    F: FnOnce(HookContext) -> HookContext,
+{
+    let handle = app_handle()?;
+    let session_type = handle
+        .state::<Mutex<AppState>>()
+        .lock()?
+        .session
+        .current_session();
+    let scripts = get_scripts(event, &session_type);
+    if scripts.is_empty() {
+        return Ok(Vec::new());
+    }
+    let context = {
+        let stopwatch = handle.state::<Mutex<Stopwatch>>();
+        let stopwatch = stopwatch.lock()?;
+        let state = handle.state::<Mutex<AppState>>();
+        let state = state.lock()?;
+        let mut context = HookContext::new(event, &stopwatch, &state.session);
+        context.focus_rating = state.focus_rating.clone();
+        context.note = state.note.clone();
+        extend(context)
+    };
+    let settings = config::current().hook(event);
+    let context = context.with_frontmost(frontmost_window());
+    let executor = handle.state::<CommandExecutor>();
+    let mut pending = Vec::new();
+    for path in scripts {
+        // A hook that cannot be queued must not keep the timer from moving on.
+        match executor.execute(path, context.clone(), settings.clone()) {
+            Ok(hook) => pending.push(hook),
+            Err(e) => notify_on_error!(e),
+        }
     }
-    let handle = APP_HANDLE.get().unwrap();
+    Ok(pending)
+}
+
+/// Runs the scripts for `event` and waits for what they hand back.
+fn run_hook_and_wait(event: &str) -> Result<HookResponse> {
+    Ok(hooks::responses(run_hook(event)?))
+}
+
+fn current_status() -> Result<Status> {
+    let handle = app_handle()?;
     let stopwatch = handle.state::<Mutex<Stopwatch>>();
-    let mut stopwatch = stopwatch.lock().unwrap();
-    stopwatch.start();
+    let stopwatch = stopwatch.lock()?;
     let state = handle.state::<Mutex<AppState>>();
-    let mut state = state.lock().unwrap();
-    state.session.start_session();
+    let state = state.lock()?;
+    Ok(Status::new(&stopwatch, &state.session))
 }
 
-#[tauri::command]
-fn on_stop() {
-    if let Some(path) = get_script("on_stop") {
-        run_script(path);
+/// Refuses early when `transition` is not allowed, before any hook runs.
+fn check(transition: Transition) -> Result<()> {
+    app_handle()?
+        .state::<Mutex<AppState>>()
+        .lock()?
+        .session
+        .check(transition)
+}
+
+/// Moves the session along `transition` and sets the stopwatch to match.
+fn step(transition: Transition) -> Result<SessionEvent> {
+    step_with(transition, |_, _| {})
+}
+
+/// Like `step`, then lets `update` adjust the stopwatch and state while both
+/// are still locked.
+fn step_with<F>(transition: Transition, update: F) -> Result<SessionEvent>
+where
+    F: FnOnce(&mut Stopwatch, &mut AppState),
+{
+    let handle = app_handle()?;
+    let event = {
+        let stopwatch = handle.state::<Mutex<Stopwatch>>();
+        let mut stopwatch = stopwatch.lock()?;
+        let state = handle.state::<Mutex<AppState>>();
+        let mut state = state.lock()?;
+        let elapsed = Duration::from_millis(stopwatch.elapsed() as u64);
+        let event = state.session.apply_timed(transition, elapsed)?;
+        stopwatch.follow(&event.to);
+        update(&mut stopwatch, &mut state);
+        event
+    };
+    announce(&event)?;
+    Ok(event)
+}
+
+/// Tells the frontend, the `on_transition` scripts and IPC subscribers that
+/// the session moved on.
+fn announce(event: &SessionEvent) -> Result<()> {
+    app_handle()?.emit("session_transition", event)?;
+    queue_hook("on_transition", Some(event.transition))?;
+    state_changed();
+    Ok(())
+}
+
+/// Pushes the current status to every IPC subscriber.
+fn state_changed() {
+    #[cfg(unix)]
+    match current_status() {
+        Ok(status) => ipc::publish(&status),
+        Err(e) => notify_on_error!(e),
     }
-    let handle = APP_HANDLE.get().unwrap();
-    let stopwatch = handle.state::<Mutex<Stopwatch>>();
-    let mut stopwatch = stopwatch.lock().unwrap();
-    stopwatch.stop();
+}
+
+/// Applies a newly loaded configuration to the live session and tells the
+/// frontend about it.
+fn apply_config(config: &config::Config) -> Result<()> {
+    let handle = app_handle()?;
+    handle
+        .state::<Mutex<AppState>>()
+        .lock()?
+        .session
+        .apply_config(config);
+    handle.emit("config_changed", config)?;
+    state_changed();
+    Ok(())
+}
+
+/// Builds the journal entry for the session the stopwatch is currently timing.
+fn journal_entry(
+    stopwatch: &Stopwatch,
+    session: &Session,
+    app_name: Option<String>,
+) -> Option<NewJournalEntry> {
+    Some(NewJournalEntry {
+        started_at: stopwatch.started_at()?,
+        ended_at: stopwatch.clock().wall(),
+        session_type: session.current_session(),
+        is_break: session.segment().is_break(),
+        paused_ms: stopwatch.paused_time() as u64,
+        suspended_ms: stopwatch.suspended().as_millis() as u64,
+        app_name,
+        focus_rating: None,
+        note: None,
+        tags: Vec::new(),
+    })
+}
+
+/// Writes the session the stopwatch is currently timing to the journal, with
+/// the note and tags the hooks asked for.
+fn record_session(response: &HookResponse) -> Result<Option<i64>> {
+    let handle = app_handle()?;
+    let entry = {
+        let stopwatch = handle.state::<Mutex<Stopwatch>>();
+        let stopwatch = stopwatch.lock()?;
+        let state = handle.state::<Mutex<AppState>>();
+        let state = state.lock()?;
+        let app_name = Some(state.app_name.clone()).filter(|name| !name.is_empty());
+        journal_entry(&stopwatch, &state.session, app_name).map(|entry| NewJournalEntry {
+            focus_rating: state.focus_rating.clone(),
+            // What the user wrote wins over what a hook suggested.
+            note: state.note.clone().or_else(|| response.note.clone()),
+            tags: response.tags.clone(),
+            ..entry
+        })
+    };
+    let Some(entry) = entry else {
+        return Ok(None);
+    };
+    let journal = handle.state::<Mutex<Journal>>();
+    let journal = journal.lock()?;
+    journal.insert(&entry).map(Some)
+}
+
+/// Logs the current session, optionally with the user's focus rating.
+///
+/// Both the `on_log` and `on_stop` hooks run before the session is written,
+/// so either can attach a note or tags to it.
+#[tauri::command(async)]
+fn on_log(focus_rating: Option<String>) -> Result<()> {
+    log_session(focus_rating, None)
+}
+
+fn log_session(focus_rating: Option<String>, note: Option<String>) -> Result<()> {
+    check(Transition::Log)?;
+    {
+        let handle = app_handle()?;
+        let state = handle.state::<Mutex<AppState>>();
+        let mut state = state.lock()?;
+        state.focus_rating = focus_rating;
+        state.note = note;
+    }
+    let mut response = run_hook_and_wait("on_log")?;
+    response.merge(run_hook_and_wait("on_stop")?);
+    if let Err(e) = record_session(&response) {
+        notify_on_error!(format!("failed to record session: {}", e));
+    }
+    finish_session(Transition::Log, &response)
+}
+
+/// Records the user's focus rating, and an optional note, for the work
+/// session that just finished. A session still waiting for its rating is
+/// logged with it; otherwise the rating replaces that of the latest logged
+/// work session. The `on_rate` scripts then run with the flow streak and
+/// daily goal it leads to, which are also returned.
+#[tauri::command(async)]
+fn rate_session(rating: FocusRating, note: Option<String>) -> Result<FocusStats> {
+    let handle = app_handle()?;
+    let awaiting =
+        handle.state::<Mutex<AppState>>().lock()?.session.phase() == Phase::AwaitingRating;
+    if awaiting {
+        log_session(Some(rating.as_str().to_string()), note.clone())?;
+    } else if handle
+        .state::<Mutex<Journal>>()
+        .lock()?
+        .rate_latest(rating, note.as_deref())?
+        .is_none()
+    {
+        return Err(MyttError::State(
+            "there is no finished work session to rate".to_string(),
+        ));
+    }
+
+    let stats = get_focus_stats(handle.state())?;
+    // Only the rating that reaches the goal announces it.
+    if rating == FocusRating::Flow && stats.goal_met() && stats.flow_today == stats.daily_goal {
+        notify!(format!(
+            "Daily flow goal reached: {} flow sessions today",
+            stats.flow_today
+        ));
+    }
+    queue_hook_with("on_rate", |context| {
+        context.with_rating(rating, note, stats.clone())
+    })?;
+    handle.emit("focus_stats", &stats)?;
+    Ok(stats)
+}
+
+/// The flow streak and how far today is towards the daily flow goal.
+#[tauri::command]
+fn get_focus_stats(journal: tauri::State<Mutex<Journal>>) -> Result<FocusStats> {
+    adaptive::focus_stats(&config::current(), &*journal.lock()?, Local::now())
+}
+
+#[tauri::command]
+fn list_sessions(
+    journal: tauri::State<Mutex<Journal>>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> Result<Vec<JournalEntry>> {
+    journal
+        .lock()?
+        .list(limit.unwrap_or(50), offset.unwrap_or(0))
+}
+
+#[tauri::command]
+fn get_session(journal: tauri::State<Mutex<Journal>>, id: i64) -> Result<Option<JournalEntry>> {
+    journal.lock()?.get(id)
+}
+
+#[tauri::command]
+fn delete_session(journal: tauri::State<Mutex<Journal>>, id: i64) -> Result<bool> {
+    journal.lock()?.delete(id)
+}
+
+/// Starts the current session. `seconds` is the length the UI planned for
+/// it, which does not replace one a hook or the user already chose, and
+/// does not apply to flowtime work.
+#[tauri::command]
+fn on_start(seconds: Option<u64>) -> Result<Status> {
+    check(Transition::Start)?;
+    run_hook("on_start")?;
+    step_with(Transition::Start, |_, state| {
+        let session = &mut state.session;
+        if let (Some(seconds), None) = (seconds, session.target_override) {
+            if !session.is_open_ended() {
+                session.set_target_duration(seconds);
+            }
+        }
+    })?;
+    current_status()
+}
+
+/// Suggests the length of the next work session and its break from the
+/// focus ratings so far. `rating` is one just given and not yet logged.
+#[tauri::command]
+fn suggest_durations(rating: Option<FocusRating>) -> Result<Suggestion> {
+    let handle = app_handle()?;
     let state = handle.state::<Mutex<AppState>>();
-    let mut state = state.lock().unwrap();
-    state.session.start_session();
+    let state = state.lock()?;
+    let journal = handle.state::<Mutex<Journal>>();
+    let journal = journal.lock()?;
+    adaptive::suggest_next(&config::current(), &state.session, &journal, rating)
 }
 
+/// Every hook event with its scripts and how they last ran.
 #[tauri::command]
-fn on_resume() {
-    if let Some(path) = get_script("on_resume") {
-        run_script(path);
-    }
-    let handle = APP_HANDLE.get().unwrap();
-    let stopwatch = handle.state::<Mutex<Stopwatch>>();
-    let mut stopwatch = stopwatch.lock().unwrap();
-    stopwatch.resume();
+fn list_hooks() -> Result<Vec<hooks::HookInfo>> {
+    hooks::inventory(
+        &config::SCRIPTS_DIR,
+        &config::current(),
+        &HookLog::default(),
+    )
+}
+
+/// Kills the hook scripts that are running right now.
+#[tauri::command]
+fn cancel_hooks(executor: tauri::State<CommandExecutor>) {
+    executor.cancel_running();
+}
+
+/// Recent hook script runs, newest first.
+#[tauri::command]
+fn list_hook_runs(limit: Option<usize>) -> Result<Vec<HookRun>> {
+    HookLog::default().recent(limit.unwrap_or(50))
+}
+
+#[tauri::command]
+fn get_status() -> Result<Status> {
+    current_status()
+}
+
+/// Overrides the length of the current session, e.g. when the user drags
+/// the progress bar.
+#[tauri::command]
+fn set_session_duration(state: tauri::State<Mutex<AppState>>, seconds: u64) -> Result<()> {
+    state.lock()?.session.set_target_duration(seconds);
+    state_changed();
+    Ok(())
+}
+
+/// Stops the session without logging it. A stopped work session does not
+/// count towards the long break; a stopped break is over.
+#[tauri::command(async)]
+fn on_stop() -> Result<()> {
+    check(Transition::Stop)?;
+    let response = run_hook_and_wait("on_stop")?;
+    finish_session(Transition::Stop, &response)
+}
+
+/// Ends the session along `transition`, with the next one set to the length
+/// a hook asked for if it did.
+fn finish_session(transition: Transition, response: &HookResponse) -> Result<()> {
+    step_with(transition, |_, state| {
+        if let Some(seconds) = response.next_duration {
+            state.session.set_target_duration(seconds);
+        }
+        state.focus_rating = None;
+        state.note = None;
+        // Time away that was never settled stays off the logged session.
+        state.idle = None;
+    })
+    .map(|_| ())
 }
 
 #[tauri::command]
-fn on_skip_break() {
-    if let Some(path) = get_script("on_skip_break") {
-        run_script(path);
+fn on_resume() -> Result<()> {
+    check(Transition::Resume)?;
+    run_hook("on_resume")?;
+    step(Transition::Resume).map(|_| ())
+}
+
+/// Skips the current or upcoming break, unless an `on_skip_break` hook
+/// vetoes it. The work session that follows is left for `on_start`.
+#[tauri::command(async)]
+fn on_skip_break() -> Result<()> {
+    check(Transition::SkipBreak)?;
+    let response = run_hook_and_wait("on_skip_break")?;
+    if response.veto {
+        let reason = response
+            .reason
+            .unwrap_or_else(|| "a hook refused to skip the break".to_string());
+        notify!(reason.clone());
+        return Err(MyttError::Vetoed(reason));
     }
-    // let handle = APP_HANDLE.get().unwrap();
-    // let state = handle.state::<Mutex<AppState>>();
-    // let mut state = state.lock().unwrap();
-    // state.session.skip_break();
-    on_start();
+    step_with(Transition::SkipBreak, |_, state| {
+        if let Some(seconds) = response.next_duration {
+            state.session.set_target_duration(seconds);
+        }
+    })
+    .map(|_| ())
+}
+
+#[tauri::command]
+fn on_pause() -> Result<()> {
+    check(Transition::Pause)?;
+    run_hook("on_pause")?;
+    step(Transition::Pause).map(|_| ())
+}
+
+/// Pauses a work session the user walked away from `idle` ago. The time away
+/// comes off the clock until the user says what it was.
+fn pause_for_idle(idle: Duration) -> Result<()> {
+    check(Transition::Pause)?;
+    run_hook("on_pause")?;
+    let period = IdlePeriod::since(idle);
+    step_with(Transition::Pause, |stopwatch, state| {
+        stopwatch.remove_elapsed(idle);
+        state.idle = Some(period.clone());
+    })?;
+    app_handle()?.emit("idle_started", &period)?;
+    Ok(())
+}
+
+/// Notes that the user is back and asks what their time away was.
+fn return_from_idle(idle: Duration) -> Result<()> {
+    let handle = app_handle()?;
+    let period = {
+        let state = handle.state::<Mutex<AppState>>();
+        let mut state = state.lock()?;
+        let Some(period) = state.idle.as_mut() else {
+            return Ok(());
+        };
+        period.ended_at = Some(IdlePeriod::since(idle).started_at);
+        period.clone()
+    };
+    notify!(format!(
+        "Welcome back! You were away for {}. Keep it, discard it or log it as a break?",
+        format_millis(period.duration().as_millis())
+    ));
+    handle.emit("idle_ended", &period)?;
+    Ok(())
 }
 
+/// Settles the time away from a work session and resumes it. `Reassign`
+/// logs the time as an entry of `session_type`, a short break by default.
 #[tauri::command]
-fn on_pause() {
-    if let Some(path) = get_script("on_pause") {
-        run_script(path);
+fn resolve_idle(action: IdleAction, session_type: Option<SessionType>) -> Result<()> {
+    let handle = app_handle()?;
+    let mut period = handle
+        .state::<Mutex<AppState>>()
+        .lock()?
+        .idle
+        .take()
+        .ok_or_else(|| MyttError::State("there is no time away to resolve".to_string()))?;
+    period.ended_at.get_or_insert_with(Local::now);
+    match action {
+        IdleAction::Keep => handle
+            .state::<Mutex<Stopwatch>>()
+            .lock()?
+            .add_elapsed(period.duration()),
+        IdleAction::Discard => {}
+        IdleAction::Reassign => {
+            let session_type = session_type.unwrap_or(SessionType::ShortBreak);
+            let is_break = handle
+                .state::<Mutex<AppState>>()
+                .lock()?
+                .session
+                .sequence()
+                .is_break(&session_type);
+            let entry = NewJournalEntry {
+                started_at: period.started_at,
+                ended_at: period.ended_at.unwrap_or_else(Local::now),
+                session_type,
+                is_break,
+                paused_ms: 0,
+                suspended_ms: 0,
+                app_name: None,
+                focus_rating: None,
+                note: None,
+                tags: vec!["idle".to_string()],
+            };
+            handle.state::<Mutex<Journal>>().lock()?.insert(&entry)?;
+        }
     }
-    let handle = APP_HANDLE.get().unwrap();
-    let stopwatch = handle.state::<Mutex<Stopwatch>>();
-    let mut stopwatch = stopwatch.lock().unwrap();
-    stopwatch.pause();
+    handle.emit("idle_resolved", &action)?;
+    let paused = matches!(
+        handle.state::<Mutex<AppState>>().lock()?.session.phase(),
+        Phase::Paused(_)
+    );
+    if paused {
+        return on_resume();
+    }
+    state_changed();
+    Ok(())
+}
+
+fn main_window(handle: &AppHandle) -> Result<tauri::WebviewWindow> {
+    handle
+        .get_webview_window("main")
+        .ok_or_else(|| MyttError::State("main window not found".to_string()))
 }
 
 #[tauri::command]
-fn on_focus(state: tauri::State<'_, Mutex<AppState>>) {
-    let front_app = state.lock().unwrap();
-    let handle = APP_HANDLE.get().unwrap();
-    let window = handle.get_webview_window("main").unwrap();
-    window
-        .emit("on_focus", Some(front_app.app_name.clone()))
-        .unwrap();
+fn on_focus(state: tauri::State<'_, Mutex<AppState>>) -> Result<()> {
+    let front_app = state.lock()?;
+    let window = main_window(app_handle()?)?;
+    window.emit("on_focus", Some(front_app.app_name.clone()))?;
+    Ok(())
 }
 
 #[tauri::command]
-fn on_blur(state: tauri::State<'_, Mutex<AppState>>) {
-    let mut front_app = state.lock().unwrap();
-    front_app.app_name = get_frontmost_application().unwrap_or_else(|| "mytt".to_string());
-    let handle = APP_HANDLE.get().unwrap();
-    let window = handle.get_webview_window("main").unwrap();
-    window
-        .emit("on_blur", Some(front_app.app_name.clone()))
-        .unwrap();
+fn on_blur(state: tauri::State<'_, Mutex<AppState>>) -> Result<()> {
+    let mut front_app = state.lock()?;
+    let window = frontmost_window();
+    front_app.app_name = window
+        .as_ref()
+        .map(|w| w.name.clone())
+        .unwrap_or_else(|| "mytt".to_string());
+    front_app.window_title = window.and_then(|w| w.title);
+    let window = main_window(app_handle()?)?;
+    window.emit("on_blur", Some(front_app.app_name.clone()))?;
+    Ok(())
 }
 
 fn on_window_event(window: &Window, event: &WindowEvent) {
     let app_handle = window.app_handle();
     let state = app_handle.state::<Mutex<AppState>>();
-    match event {
-        WindowEvent::Focused(true) => {
-            on_focus(state);
-        }
-        WindowEvent::Focused(false) => {
-            on_blur(state);
-        }
-        _ => {}
+    let result = match event {
+        WindowEvent::Focused(true) => on_focus(state),
+        WindowEvent::Focused(false) => on_blur(state),
+        _ => Ok(()),
+    };
+    if let Err(e) = result {
+        notify_on_error!(e);
     }
 }
 
 #[tauri::command]
-fn get_frontmost_application() -> Option<String> {
+fn get_frontmost_application() -> Result<Option<String>> {
+    Ok(frontmost_application_name())
+}
+
+/// Like `get_frontmost_application`, but with the focused window's title
+/// where the platform backend can provide it.
+#[tauri::command]
+fn get_frontmost_window() -> Result<Option<FrontmostApp>> {
+    Ok(frontmost_window())
+}
+
+fn frontmost_application_name() -> Option<String> {
     #[cfg(target_os = "macos")]
     {
         let shared_workspace: id = unsafe { msg_send![class!(NSWorkspace), sharedWorkspace] };
@@ -322,111 +1061,85 @@ fn get_frontmost_application() -> Option<String> {
         unsafe { nsstring_to_string!(app_name) }
     }
 
-    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    #[cfg(target_os = "linux")]
+    {
+        frontmost::frontmost_application().map(|app| app.name)
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
     {
         None
     }
 }
 
+fn frontmost_window() -> Option<FrontmostApp> {
+    #[cfg(target_os = "linux")]
+    {
+        frontmost::frontmost_application()
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        frontmost_application_name().map(|name| FrontmostApp { name, title: None })
+    }
+}
+
 #[derive(Default)]
 struct AppState {
     app_name: String,
+    window_title: Option<String>,
+    /// Rating given to the session being logged.
+    focus_rating: Option<String>,
+    /// Note given with that rating.
+    note: Option<String>,
+    /// Time away from the current session that is still to be settled.
+    idle: Option<IdlePeriod>,
     session: Session,
 }
 
 impl AppState {
     fn new() -> Self {
         Self {
-            app_name: get_frontmost_application().unwrap_or_else(|| "mytt".to_string()),
-            session: Session::new(),
+            app_name: frontmost_application_name().unwrap_or_else(|| "mytt".to_string()),
+            window_title: None,
+            focus_rating: None,
+            note: None,
+            idle: None,
+            session: Session::with_config(&config::current()),
         }
     }
 }
 
-#[derive(Default, PartialEq)]
-enum SessionType {
-    #[default]
-    Work,
-    ShortBreak,
-    LongBreak,
-}
-
-#[derive(Default)]
-struct Session {
-    short_break_time: u64,
-    long_break_time: u64,
-    work_time: u64,
-    work_sessions: u64,
-    long_break_sessions: u64,
-    current_session: SessionType,
-}
-
-impl Session {
-    fn new() -> Self {
-        Self {
-            short_break_time: 5,
-            long_break_time: 15,
-            work_time: 25,
-            work_sessions: 0,
-            long_break_sessions: 0,
-            current_session: SessionType::Work,
-        }
-    }
-
-    fn start_session(&mut self) {
-        match self.current_session {
-            SessionType::Work => {
-                // Start work session
-                self.work_sessions += 1;
-                if self.work_sessions % 4 == 0 {
-                    self.current_session = SessionType::LongBreak;
-                } else {
-                    self.current_session = SessionType::ShortBreak;
-                }
-            }
-            SessionType::ShortBreak => {
-                // Start short break session
-                self.current_session = SessionType::Work;
-            }
-            SessionType::LongBreak => {
-                // Start long break session
-                self.long_break_sessions += 1;
-                self.current_session = SessionType::Work;
-            }
-        }
-    }
-
-    fn get_session_duration(&self) -> u64 {
-        match self.current_session {
-            SessionType::Work => self.work_time,
-            SessionType::ShortBreak => self.short_break_time,
-            SessionType::LongBreak => self.long_break_time,
-        }
-    }
-
-    fn skip_break(&mut self) {
-        match self.current_session {
-            SessionType::Work => {
-                self.current_session = SessionType::ShortBreak;
-            }
-            SessionType::ShortBreak => {
-                self.current_session = SessionType::Work;
-            }
-            SessionType::LongBreak => {
-                self.current_session = SessionType::Work;
-            }
+pub fn main() {
+    if let Some(command) = cli::parse() {
+        if let Err(e) = cli::run(command) {
+            eprintln!("mytt: {:#}", e);
+            std::process::exit(1);
         }
+        return;
     }
-}
 
-pub fn main() {
     Builder::default()
         .plugin(tauri_plugin_notification::init())
         .setup(move |app| {
             app.manage(Mutex::new(Stopwatch::new()));
             app.manage(CommandExecutor::new());
-            app.manage(Mutex::new(AppState::default()));
+            app.manage(Mutex::new(AppState::new()));
+            let journal = Journal::open_default().or_else(|e| {
+                notify_on_error!(format!("session journal unavailable, using memory: {}", e));
+                Journal::open_in_memory()
+            })?;
+            app.manage(Mutex::new(journal));
             APP_HANDLE.set(app.handle().clone()).unwrap();
+            #[cfg(unix)]
+            if let Err(e) = ipc::serve(ipc::socket_path(), ipc::dispatch) {
+                notify_on_error!(format!("failed to start control socket: {}", e));
+            }
+            countdown::spawn(app.handle().clone());
+            idle::spawn();
+            if let Err(e) = watcher::spawn() {
+                notify_on_error!(format!("config changes will need a restart: {}", e));
+            }
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -436,6 +1149,8 @@ pub fn main() {
             reset_timer,
             pause_timer,
             resume_timer,
+            lap_timer,
+            get_laps,
             on_start,
             on_stop,
             on_pause,
@@ -445,11 +1160,35 @@ pub fn main() {
             on_blur,
             on_log,
             fetch_config,
+            validate_config,
+            save_config,
+            list_sequences,
+            select_sequence,
             get_frontmost_application,
+            get_frontmost_window,
+            get_status,
+            set_session_duration,
+            suggest_durations,
+            rate_session,
+            get_focus_stats,
+            list_sessions,
+            get_session,
+            delete_session,
+            list_hook_runs,
+            cancel_hooks,
+            list_hooks,
+            resolve_idle,
         ])
         .on_window_event(|window, event| {
             on_window_event(window, event);
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|handle, event| {
+            if let RunEvent::Exit = event {
+                handle
+                    .state::<CommandExecutor>()
+                    .shutdown(Some(HOOK_SHUTDOWN_GRACE));
+            }
+        });
 }
@@ -0,0 +1,189 @@
+//! Hooks written in Rhai that run inside the app instead of a shell.
+//!
+//! `on_<event>.rhai` in `SCRIPTS_DIR`, or any `*.rhai` file in
+//! `on_<event>.d/`, runs in order with the shell hooks for the same event and
+//! through the same queue, log and timeouts. A script sees the hook context
+//! as the `ctx` map and can only reach outside the engine through the
+//! functions registered here:
+//!
+//! - `notify(message)` shows a notification
+//! - `journal(limit)` returns the most recent journal entries, newest first
+//! - `write_file(path, text)` and `append_file(path, text)` write files under
+//!   the home directory, `~/` included
+//!
+//! `print` and `debug` become the run's output, and a map the script ends
+//! with is read as a [`HookResponse`].
+use crate::config::HOME_DIR;
+use crate::hooks::{HookContext, HookResponse, RunningHooks};
+use crate::journal::Journal;
+use crate::notify;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Scope};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+pub const EXTENSION: &str = "rhai";
+
+// Values `on_progress` stops a script with, to tell the two reasons apart.
+const TIMED_OUT: &str = "timed out";
+const KILLED: &str = "killed";
+
+type ScriptResult<T> = std::result::Result<T, Box<EvalAltResult>>;
+
+/// Whether `path` is run by the embedded engine rather than executed.
+pub fn is_embedded(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == EXTENSION)
+}
+
+#[derive(Debug, Default)]
+pub struct ScriptOutput {
+    /// Everything the script printed.
+    pub output: String,
+    pub response: Option<HookResponse>,
+    /// Why the script failed, if it did.
+    pub error: Option<String>,
+    /// Whether the script was stopped, by its timeout or `kill_all`.
+    pub terminated: bool,
+    pub timed_out: bool,
+}
+
+/// Runs `script` for `context`, stopping it once `timeout` has passed.
+pub fn run(
+    script: &Path,
+    context: &HookContext,
+    timeout: Option<Duration>,
+    running: &RunningHooks,
+) -> ScriptOutput {
+    let printed = Arc::new(Mutex::new(String::new()));
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let engine = engine(Arc::clone(&printed), deadline, running.watch_kills());
+    let result = evaluate(&engine, script, context);
+
+    let mut output = ScriptOutput {
+        output: printed.lock().map(|p| p.clone()).unwrap_or_default(),
+        ..Default::default()
+    };
+    match result {
+        Ok(value) if value.is_map() => match rhai::serde::from_dynamic(&value) {
+            Ok(response) => output.response = Some(response),
+            Err(e) => output.error = Some(format!("invalid response: {}", e)),
+        },
+        Ok(_) => {}
+        Err(e) => {
+            if let EvalAltResult::ErrorTerminated(reason, _) = e.as_ref() {
+                output.terminated = true;
+                output.timed_out = reason.to_string() == TIMED_OUT;
+            }
+            output.error = Some(e.to_string());
+        }
+    }
+    output
+}
+
+fn evaluate(engine: &Engine, script: &Path, context: &HookContext) -> ScriptResult<Dynamic> {
+    let ast = engine.compile_file(script.to_path_buf())?;
+    let mut scope = Scope::new();
+    scope.push_constant("ctx", rhai::serde::to_dynamic(context)?);
+    engine.eval_ast_with_scope(&mut scope, &ast)
+}
+
+fn engine(
+    printed: Arc<Mutex<String>>,
+    deadline: Option<Instant>,
+    killed: impl Fn() -> bool + 'static,
+) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_call_levels(64);
+    engine.set_max_expr_depths(64, 32);
+    engine.disable_symbol("eval");
+
+    let debug = Arc::clone(&printed);
+    engine.on_print(move |text| print_line(&printed, text));
+    engine.on_debug(move |text, _, _| print_line(&debug, text));
+    engine.on_progress(move |_| {
+        if killed() {
+            Some(KILLED.into())
+        } else if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            Some(TIMED_OUT.into())
+        } else {
+            None
+        }
+    });
+
+    engine.register_fn("notify", |message: &str| notify!(message.to_string()));
+    engine.register_fn("journal", journal);
+    engine.register_fn("write_file", |path: &str, text: &str| {
+        write_file(path, text, false)
+    });
+    engine.register_fn("append_file", |path: &str, text: &str| {
+        write_file(path, text, true)
+    });
+    engine
+}
+
+fn print_line(printed: &Mutex<String>, text: &str) {
+    if let Ok(mut printed) = printed.lock() {
+        printed.push_str(text);
+        printed.push('\n');
+    }
+}
+
+fn journal(limit: i64) -> ScriptResult<Array> {
+    let limit = u32::try_from(limit.max(0)).unwrap_or(u32::MAX);
+    let entries = Journal::open_default()
+        .and_then(|journal| journal.list(limit, 0))
+        .map_err(|e| e.to_string())?;
+    entries.iter().map(rhai::serde::to_dynamic).collect()
+}
+
+fn write_file(path: &str, text: &str, append: bool) -> ScriptResult<()> {
+    let target =
+        home_path(path).ok_or_else(|| format!("{} is outside the home directory", path))?;
+    let write = || -> std::io::Result<()> {
+        if let Some(dir) = target.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(&target)?
+            .write_all(text.as_bytes())
+    };
+    write().map_err(|e| format!("cannot write {}: {}", path, e).into())
+}
+
+/// `path` with `~/` expanded, as long as it stays inside `home`, symlinks
+/// followed.
+pub(crate) fn resolve_in(home: &Path, path: &str) -> Option<PathBuf> {
+    let path = match path.strip_prefix("~/") {
+        Some(rest) => home.join(rest),
+        None => PathBuf::from(path),
+    };
+    let escapes = path.components().any(|c| c == Component::ParentDir);
+    if !path.starts_with(home) || escapes {
+        return None;
+    }
+    // A symlink under `home` can still lead out of it, so what counts is
+    // where the nearest part of the path that exists really is.
+    let Some(existing) = path
+        .ancestors()
+        .take_while(|p| p.starts_with(home))
+        .find(|p| p.symlink_metadata().is_ok())
+    else {
+        return Some(path);
+    };
+    let home = home.canonicalize().ok()?;
+    existing
+        .canonicalize()
+        .ok()?
+        .starts_with(home)
+        .then_some(path)
+}
+
+fn home_path(path: &str) -> Option<PathBuf> {
+    resolve_in(&HOME_DIR, path)
+}
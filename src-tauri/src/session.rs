@@ -0,0 +1,560 @@
+//! The work/break cycle as a state machine.
+//!
+//! A [`Session`] is always in one [`Phase`] and only moves between phases
+//! through a [`Transition`]. Transitions that make no sense from the current
+//! phase (pausing while idle, logging nothing, skipping a work session) are
+//! refused with an error instead of quietly advancing the cycle. Every
+//! transition that goes through yields a [`SessionEvent`], which the app
+//! emits to the frontend and the `on_transition` hook.
+//!
+//! Which session comes next is up to the [`Sequence`]: the classic Pomodoro
+//! cycle built from the configured durations, or one of the `sequences`
+//! defined in `config.yaml`, such as 52/17 or 90-minute blocks. In the
+//! built-in `flowtime` sequence work has no set length; it counts up until
+//! it is stopped, and the break after it is worked out from how long it ran.
+//!
+//! The stopwatch is kept separately and follows the phase, see
+//! [`Stopwatch::follow`](crate::Stopwatch::follow).
+use crate::config::{Config, Flowtime, Segment};
+use crate::error::{MyttError, Result};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// The kind of a session. Sequences in `config.yaml` may name kinds of
+/// their own, which are kept as `Custom`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum SessionType {
+    #[default]
+    Work,
+    ShortBreak,
+    LongBreak,
+    Custom(String),
+}
+
+impl SessionType {
+    pub(crate) fn as_str(&self) -> &str {
+        match self {
+            SessionType::Work => "work",
+            SessionType::ShortBreak => "shortBreak",
+            SessionType::LongBreak => "longBreak",
+            SessionType::Custom(name) => name,
+        }
+    }
+
+    /// Whether this kind is a break unless a sequence says otherwise.
+    pub fn is_break(&self) -> bool {
+        matches!(self, SessionType::ShortBreak | SessionType::LongBreak)
+    }
+}
+
+impl FromStr for SessionType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "work" => Ok(SessionType::Work),
+            "shortBreak" | "short_break" => Ok(SessionType::ShortBreak),
+            "longBreak" | "long_break" => Ok(SessionType::LongBreak),
+            "" => Err("session type must not be empty".to_string()),
+            name => Ok(SessionType::Custom(name.to_string())),
+        }
+    }
+}
+
+impl Serialize for SessionType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for SessionType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Where the session is. Breaks run and pause like work sessions do, as
+/// `Running(ShortBreak)` and so on.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(tag = "phase", content = "session_type", rename_all = "snake_case")]
+pub enum Phase {
+    /// Nothing is being timed; `current_session` is up next.
+    #[default]
+    Idle,
+    Running(SessionType),
+    Paused(SessionType),
+    /// A work session ran its full length and waits to be logged with a
+    /// focus rating, or stopped.
+    AwaitingRating,
+}
+
+impl Phase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Phase::Idle => "idle",
+            Phase::Running(_) => "running",
+            Phase::Paused(_) => "paused",
+            Phase::AwaitingRating => "awaiting_rating",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Transition {
+    /// Start timing the session up next.
+    Start,
+    Pause,
+    Resume,
+    /// The session ran its planned length.
+    Complete,
+    /// Stop without logging. Abandoning a work session does not count it.
+    Stop,
+    /// Log the session and move on to the next one.
+    Log,
+    /// End the current or upcoming break and make work the next session.
+    SkipBreak,
+}
+
+impl Transition {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Transition::Start => "start",
+            Transition::Pause => "pause",
+            Transition::Resume => "resume",
+            Transition::Complete => "complete",
+            Transition::Stop => "stop",
+            Transition::Log => "log",
+            Transition::SkipBreak => "skip_break",
+        }
+    }
+}
+
+/// A transition that went through, as sent to the frontend and hooks.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct SessionEvent {
+    pub transition: Transition,
+    pub from: Phase,
+    pub to: Phase,
+    /// The session the transition applied to.
+    pub session_type: SessionType,
+    /// The session being timed or up next afterwards.
+    pub current_session: SessionType,
+}
+
+/// What a transition does to the cycle besides changing the phase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cycle {
+    Stay,
+    /// The session is over; move on to the next one.
+    Advance,
+    /// Drop the break and go on to the next session that is not one.
+    SkipBreak,
+}
+
+/// The cycle a session runs through, segment by segment.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Sequence {
+    pub name: String,
+    #[serde(deserialize_with = "non_empty")]
+    pub segments: Vec<Segment>,
+    /// Set for flowtime: work is open-ended and breaks follow these rules.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flowtime: Option<Flowtime>,
+}
+
+impl Default for Sequence {
+    fn default() -> Self {
+        Self::classic(&Config::default())
+    }
+}
+
+lazy_static! {
+    /// What a session falls back on if its own sequence has no segments.
+    static ref BUILT_IN: Sequence = Sequence::default();
+}
+
+/// Refuses saved sequences without segments, which nothing could be timed
+/// from.
+fn non_empty<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Segment>, D::Error> {
+    let segments = Vec::<Segment>::deserialize(deserializer)?;
+    if segments.is_empty() {
+        return Err(serde::de::Error::custom(
+            "a sequence must have at least one segment",
+        ));
+    }
+    Ok(segments)
+}
+
+impl Sequence {
+    /// The name of the built-in Pomodoro cycle.
+    pub const CLASSIC: &'static str = "classic";
+    /// The name of the built-in flowtime cycle.
+    pub const FLOWTIME: &'static str = "flowtime";
+
+    pub fn is_built_in(name: &str) -> bool {
+        name == Self::CLASSIC || name == Self::FLOWTIME
+    }
+
+    /// Work and a short break, with a long break after every
+    /// `sessions_long_break`th work session, for the durations in `config`.
+    pub fn classic(config: &Config) -> Self {
+        let segment = |session_type: SessionType, minutes: u64| Segment {
+            session_type,
+            minutes,
+            is_break: None,
+            sound: None,
+        };
+        let mut segments = Vec::new();
+        for n in 1..=config.sessions_long_break().max(1) {
+            segments.push(segment(SessionType::Work, config.work_duration()));
+            if n == config.sessions_long_break().max(1) {
+                segments.push(segment(
+                    SessionType::LongBreak,
+                    config.long_break_duration(),
+                ));
+            } else {
+                segments.push(segment(
+                    SessionType::ShortBreak,
+                    config.short_break_duration(),
+                ));
+            }
+        }
+        Self {
+            name: Self::CLASSIC.to_string(),
+            segments,
+            flowtime: None,
+        }
+        .with_sounds(config)
+    }
+
+    /// Open-ended work, then a break sized by `config.flowtime`. The break's
+    /// `minutes` only apply when there is no work to size it by.
+    pub fn flowtime(config: &Config) -> Self {
+        Self {
+            name: Self::FLOWTIME.to_string(),
+            segments: vec![
+                Segment {
+                    session_type: SessionType::Work,
+                    minutes: 0,
+                    is_break: None,
+                    sound: None,
+                },
+                Segment {
+                    session_type: SessionType::ShortBreak,
+                    minutes: config.short_break_duration(),
+                    is_break: None,
+                    sound: None,
+                },
+            ],
+            flowtime: Some(config.flowtime.clone().unwrap_or_default()),
+        }
+        .with_sounds(config)
+    }
+
+    /// The sequence `config` selects, falling back to the classic cycle when
+    /// it names none or one that is not defined.
+    pub fn from_config(config: &Config) -> Self {
+        let name = config.sequence();
+        if name == Self::FLOWTIME {
+            return Self::flowtime(config);
+        }
+        match config.sequences.as_ref().and_then(|s| s.get(name)) {
+            Some(segments) if !segments.is_empty() && !Self::is_built_in(name) => Self {
+                name: name.to_string(),
+                segments: segments.clone(),
+                flowtime: None,
+            }
+            .with_sounds(config),
+            _ => Self::classic(config),
+        }
+    }
+
+    /// Whether segments of `session_type` are breaks in this sequence.
+    pub fn is_break(&self, session_type: &SessionType) -> bool {
+        self.segments
+            .iter()
+            .find(|segment| segment.session_type == *session_type)
+            .map_or_else(|| session_type.is_break(), Segment::is_break)
+    }
+
+    /// Every sequence `config` offers, the built-in ones first.
+    pub fn all(config: &Config) -> Vec<Self> {
+        let mut sequences = vec![Self::classic(config), Self::flowtime(config)];
+        for (name, segments) in config.sequences.iter().flatten() {
+            if !Self::is_built_in(name) && !segments.is_empty() {
+                sequences.push(
+                    Self {
+                        name: name.clone(),
+                        segments: segments.clone(),
+                        flowtime: None,
+                    }
+                    .with_sounds(config),
+                );
+            }
+        }
+        sequences
+    }
+
+    /// Fills in `work_sound` and `break_sound` for segments without a sound
+    /// of their own.
+    fn with_sounds(mut self, config: &Config) -> Self {
+        for segment in &mut self.segments {
+            if segment.sound.is_none() {
+                segment.sound = if segment.is_break() {
+                    config.break_sound.clone()
+                } else {
+                    config.work_sound.clone()
+                };
+            }
+        }
+        self
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Session {
+    pub(crate) work_sessions: u64,
+    pub(crate) long_break_sessions: u64,
+    /// Planned length in seconds of the last work session that ended, which
+    /// suggestions for the next one start from.
+    #[serde(default)]
+    pub(crate) last_work: Option<u64>,
+    /// Length of the current session in seconds, when it differs from the
+    /// configured duration for its segment.
+    #[serde(default)]
+    pub(crate) target_override: Option<u64>,
+    #[serde(default)]
+    sequence: Sequence,
+    /// Index of the segment being timed, or up next while idle.
+    #[serde(default)]
+    position: usize,
+    /// A sequence selected while a session was under way, taken up once it
+    /// is over.
+    #[serde(default)]
+    pending: Option<Sequence>,
+    #[serde(default)]
+    phase: Phase,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Session {
+    /// A session with the default durations.
+    pub fn new() -> Self {
+        Self::with_config(&Config::default())
+    }
+
+    pub fn with_config(config: &Config) -> Self {
+        Self {
+            work_sessions: 0,
+            long_break_sessions: 0,
+            last_work: None,
+            target_override: None,
+            sequence: Sequence::from_config(config),
+            position: 0,
+            pending: None,
+            phase: Phase::Idle,
+        }
+    }
+
+    /// Takes the sequence and durations from `config`. Edits to the current
+    /// sequence apply right away; a different sequence starts from its first
+    /// segment once the session is idle.
+    pub fn apply_config(&mut self, config: &Config) {
+        let sequence = Sequence::from_config(config);
+        if sequence.name == self.sequence.name {
+            self.position %= sequence.segments.len();
+            self.sequence = sequence;
+            self.pending = None;
+        } else {
+            self.pending = Some(sequence);
+            self.take_pending();
+        }
+    }
+
+    fn take_pending(&mut self) {
+        if self.phase != Phase::Idle {
+            return;
+        }
+        if let Some(sequence) = self.pending.take() {
+            self.sequence = sequence;
+            self.position = 0;
+            self.target_override = None;
+        }
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase.clone()
+    }
+
+    pub fn sequence(&self) -> &Sequence {
+        &self.sequence
+    }
+
+    /// Index of the segment being timed, or up next while idle.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The segment being timed, or up next while idle.
+    pub fn segment(&self) -> &Segment {
+        // A restored session may point past the end of its sequence.
+        let segments = &self.sequence.segments;
+        segments
+            .get(self.position)
+            .or_else(|| segments.first())
+            .or_else(|| BUILT_IN.segments.first())
+            .expect("the built-in sequence has segments")
+    }
+
+    /// The kind of the segment being timed, or up next while idle.
+    pub fn current_session(&self) -> SessionType {
+        self.segment().session_type.clone()
+    }
+
+    /// Whether a segment that is not a break is running.
+    pub fn is_working(&self) -> bool {
+        matches!(self.phase, Phase::Running(_)) && !self.segment().is_break()
+    }
+
+    /// Whether the current segment is flowtime work, which runs until it is
+    /// stopped instead of counting down.
+    pub fn is_open_ended(&self) -> bool {
+        self.sequence.flowtime.is_some() && !self.segment().is_break()
+    }
+
+    /// Fails with the reason `transition` is not allowed from here.
+    pub fn check(&self, transition: Transition) -> Result<()> {
+        self.next(transition).map(|_| ())
+    }
+
+    /// Moves to the phase `transition` leads to, or fails and leaves the
+    /// session as it was.
+    pub fn apply(&mut self, transition: Transition) -> Result<SessionEvent> {
+        self.apply_timed(transition, Duration::ZERO)
+    }
+
+    /// Like `apply`, for a session that has run for `elapsed`. Ending
+    /// flowtime work sizes the break after it by `elapsed`.
+    pub fn apply_timed(
+        &mut self,
+        transition: Transition,
+        elapsed: Duration,
+    ) -> Result<SessionEvent> {
+        let (to, cycle) = self.next(transition)?;
+        let from = self.phase();
+        let session_type = self.current_session();
+        let open_ended = self.is_open_ended();
+        match cycle {
+            Cycle::Stay => {}
+            Cycle::Advance => self.advance(),
+            Cycle::SkipBreak => self.skip_break(),
+        }
+        if let (true, Cycle::Advance, Some(flowtime)) = (open_ended, cycle, &self.sequence.flowtime)
+        {
+            self.target_override = Some(flowtime.break_for(elapsed));
+        }
+        self.phase = to.clone();
+        self.take_pending();
+        Ok(SessionEvent {
+            transition,
+            from,
+            to,
+            session_type,
+            current_session: self.current_session(),
+        })
+    }
+
+    fn next(&self, transition: Transition) -> Result<(Phase, Cycle)> {
+        use Phase::*;
+        use Transition::*;
+
+        let on_break = self.segment().is_break();
+        let open_ended = self.is_open_ended();
+        let next = match (transition, &self.phase) {
+            (Start, Idle) => (Running(self.current_session()), Cycle::Stay),
+            (Pause, Running(session_type)) => (Paused(session_type.clone()), Cycle::Stay),
+            (Resume, Paused(session_type)) => (Running(session_type.clone()), Cycle::Stay),
+            (Complete, Running(_)) if on_break => (Idle, Cycle::Advance),
+            (Complete, Running(_)) => (AwaitingRating, Cycle::Stay),
+            (Log, Running(_) | Paused(_) | AwaitingRating) => (Idle, Cycle::Advance),
+            (Stop, AwaitingRating) => (Idle, Cycle::Advance),
+            // A stopped break is over, a stopped work session starts again.
+            // Flowtime work only ends by being stopped, so that counts.
+            (Stop, Running(_) | Paused(_)) if on_break || open_ended => (Idle, Cycle::Advance),
+            (Stop, Running(_) | Paused(_)) => (Idle, Cycle::Stay),
+            (SkipBreak, Idle | Running(_) | Paused(_)) if on_break => (Idle, Cycle::SkipBreak),
+            (SkipBreak, _) => {
+                return Err(MyttError::State("there is no break to skip".to_string()))
+            }
+            (transition, phase) => {
+                return Err(MyttError::State(format!(
+                    "cannot {} while {}",
+                    transition.as_str(),
+                    phase.as_str()
+                )))
+            }
+        };
+        Ok(next)
+    }
+
+    /// Moves on from a finished segment to the next one in the sequence,
+    /// counting work sessions and long breaks on the way.
+    fn advance(&mut self) {
+        let segment = self.segment();
+        let (work, long_break) = (
+            !segment.is_break(),
+            segment.session_type == SessionType::LongBreak,
+        );
+        if work {
+            self.work_sessions += 1;
+            if !self.is_open_ended() {
+                self.last_work = Some(self.target_duration());
+            }
+        }
+        if long_break {
+            self.long_break_sessions += 1;
+        }
+        self.step_forward();
+    }
+
+    /// Moves past the break, and any breaks right after it, without counting
+    /// them.
+    fn skip_break(&mut self) {
+        for _ in 0..self.sequence.segments.len() {
+            self.step_forward();
+            if !self.segment().is_break() {
+                break;
+            }
+        }
+    }
+
+    fn step_forward(&mut self) {
+        self.target_override = None;
+        self.position = (self.position + 1) % self.sequence.segments.len();
+    }
+
+    /// Configured length of the current segment in minutes.
+    pub fn get_session_duration(&self) -> u64 {
+        self.segment().minutes
+    }
+
+    /// Planned length of the current session in seconds.
+    pub fn target_duration(&self) -> u64 {
+        self.target_override
+            .unwrap_or(self.get_session_duration() * 60)
+    }
+
+    pub fn set_target_duration(&mut self, seconds: u64) {
+        self.target_override = Some(seconds);
+    }
+}
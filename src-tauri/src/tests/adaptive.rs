@@ -0,0 +1,257 @@
+// tests/adaptive.rs
+#[cfg(test)]
+mod adaptive_tests {
+    use crate::adaptive::{
+        focus_stats, suggest, suggest_next, FocusRating, FocusStats, Suggestion,
+    };
+    use crate::config::{Adaptive, Config, ConfigCheck};
+    use crate::journal::{Journal, NewJournalEntry};
+    use crate::{Session, SessionType, Transition};
+    use chrono::{DateTime, Duration, Local, TimeZone};
+    use std::collections::BTreeMap;
+
+    use FocusRating::*;
+
+    const MINUTE: u64 = 60;
+
+    #[test]
+    fn test_no_ratings_keeps_length() {
+        let config = Config::default();
+        assert_eq!(
+            suggest(&config, 20 * MINUTE, &[]),
+            Suggestion {
+                work_seconds: 20 * MINUTE,
+                break_seconds: 5 * MINUTE,
+                reason: "No focus ratings yet".to_string(),
+            }
+        );
+        // Still kept within the limits.
+        assert_eq!(suggest(&config, 90 * MINUTE, &[]).work_seconds, 50 * MINUTE);
+    }
+
+    #[test]
+    fn test_steps_shrink_as_sessions_grow() {
+        let config = Config::default();
+        // Full step between 10 and 25 minutes.
+        let focused = suggest(&config, 15 * MINUTE, &[Focused, Distracted]);
+        assert_eq!(focused.work_seconds, 20 * MINUTE);
+        assert_eq!(focused.break_seconds, 2 * MINUTE);
+        assert_eq!(focused.reason, "Increased due to good focus");
+        // Half a step under 10 minutes, a quarter from 25 on.
+        assert_eq!(suggest(&config, 5 * MINUTE, &[Ok]).work_seconds, 6 * MINUTE);
+        assert_eq!(
+            suggest(&config, 25 * MINUTE, &[Focused]).work_seconds,
+            25 * MINUTE + 75
+        );
+        let distracted = suggest(&config, 30 * MINUTE, &[Distracted]);
+        assert_eq!(distracted.work_seconds, 30 * MINUTE - 45);
+        assert_eq!(distracted.break_seconds, 5 * MINUTE);
+        assert_eq!(
+            suggest(&config, 2 * MINUTE, &[Distracted]).work_seconds,
+            2 * MINUTE
+        );
+    }
+
+    #[test]
+    fn test_flow_streak() {
+        let config = Config::default();
+        let once = suggest(&config, 20 * MINUTE, &[Flow, Ok]);
+        assert_eq!(once.work_seconds, 20 * MINUTE + 780);
+        assert_eq!(once.reason, "Extended due to flow state");
+        assert_eq!(once.break_seconds, 3 * MINUTE);
+
+        let streak = suggest(&config, 20 * MINUTE, &[Flow, Flow, Flow, Flow, Ok]);
+        assert_eq!(streak.work_seconds, 20 * MINUTE + 1140);
+        assert_eq!(streak.reason, "Flow streak (4x) bonus applied");
+        assert_eq!(streak.break_seconds, 2 * MINUTE);
+        assert_eq!(
+            suggest(&config, 48 * MINUTE, &[Flow, Flow]).work_seconds,
+            50 * MINUTE
+        );
+    }
+
+    #[test]
+    fn test_configured_steps_and_limits() {
+        let config = ConfigCheck::new(
+            "adaptive:\n  min_duration: 10\n  max_duration: 90\n  increments: { flow: 20, distracted: -10 }\n  breaks: { ok: 4 }\n",
+        )
+        .into_result()
+        .unwrap();
+        assert_eq!(
+            config.adaptive.as_ref().unwrap().increments,
+            Some(BTreeMap::from([(Distracted, -10), (Flow, 20)]))
+        );
+        assert_eq!(
+            suggest(&config, 80 * MINUTE, &[Flow]).work_seconds,
+            86 * MINUTE + 30
+        );
+        assert_eq!(
+            suggest(&config, 12 * MINUTE, &[Distracted]).work_seconds,
+            10 * MINUTE
+        );
+        let ok = suggest(&config, 12 * MINUTE, &[Ok]);
+        assert_eq!(ok.work_seconds, 14 * MINUTE);
+        assert_eq!(ok.break_seconds, 4 * MINUTE);
+    }
+
+    #[test]
+    fn test_limits_are_validated() {
+        let check = ConfigCheck::new("adaptive:\n  min_duration: 30\n  max_duration: 20\n");
+        assert!(!check.valid);
+        assert_eq!(
+            check.diagnostics[0].message,
+            "adaptive.max_duration must not be less than min_duration"
+        );
+        assert_eq!(check.diagnostics[0].line, Some(3));
+        let check = ConfigCheck::new("adaptive:\n  min_duration: 0\n");
+        assert!(!check.valid);
+        // Without validation, a maximum below the minimum gives way to it.
+        let adaptive = Adaptive {
+            min_duration: Some(30),
+            max_duration: Some(20),
+            ..Default::default()
+        };
+        assert_eq!(adaptive.max_duration(), 30);
+    }
+
+    fn rated(journal: &Journal, minutes_ago: i64, session_type: SessionType, rating: &str) {
+        rated_at(
+            journal,
+            Local::now() - Duration::minutes(minutes_ago),
+            session_type,
+            rating,
+        );
+    }
+
+    fn rated_at(
+        journal: &Journal,
+        ended_at: DateTime<Local>,
+        session_type: SessionType,
+        rating: &str,
+    ) {
+        journal
+            .insert(&NewJournalEntry {
+                started_at: ended_at - Duration::minutes(25),
+                ended_at,
+                is_break: session_type.is_break(),
+                session_type,
+                paused_ms: 0,
+                suspended_ms: 0,
+                app_name: None,
+                focus_rating: Some(rating.to_string()),
+                note: None,
+                tags: Vec::new(),
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_ratings_come_from_the_journal() {
+        let journal = Journal::open_in_memory().unwrap();
+        rated(&journal, 90, SessionType::Work, "distracted");
+        rated(&journal, 60, SessionType::Work, "flow");
+        rated(&journal, 40, SessionType::ShortBreak, "ok");
+        rated(
+            &journal,
+            30,
+            SessionType::Custom("draft".to_string()),
+            "flow",
+        );
+        rated(&journal, 10, SessionType::Work, "meh");
+        assert_eq!(
+            journal.recent_ratings(10).unwrap(),
+            [Flow, Flow, Distracted]
+        );
+
+        let config = Config::default();
+        let mut session = Session::new();
+        let suggestion = suggest_next(&config, &session, &journal, Some(Focused)).unwrap();
+        assert_eq!(suggestion.work_seconds, 25 * MINUTE + 75);
+
+        // The next suggestion starts from the length the last work session had.
+        session.set_target_duration(15 * MINUTE);
+        session.apply(Transition::Start).unwrap();
+        session.apply(Transition::Log).unwrap();
+        assert_eq!(session.last_work, Some(15 * MINUTE));
+        let suggestion = suggest_next(&config, &session, &journal, None).unwrap();
+        assert_eq!(suggestion.work_seconds, 31 * MINUTE);
+        assert_eq!(suggestion.reason, "Flow streak (2x) bonus applied");
+    }
+
+    #[test]
+    fn test_focus_stats() {
+        let now = Local.with_ymd_and_hms(2026, 3, 10, 15, 0, 0).unwrap();
+        let journal = Journal::open_in_memory().unwrap();
+        let config = Config::default();
+        assert_eq!(
+            focus_stats(&config, &journal, now).unwrap(),
+            FocusStats {
+                streak: 0,
+                flow_today: 0,
+                daily_goal: 3,
+            }
+        );
+
+        rated_at(&journal, now - Duration::days(1), SessionType::Work, "flow");
+        rated_at(
+            &journal,
+            now - Duration::hours(5),
+            SessionType::Work,
+            "focused",
+        );
+        rated_at(
+            &journal,
+            now - Duration::hours(3),
+            SessionType::Work,
+            "flow",
+        );
+        rated_at(
+            &journal,
+            now - Duration::hours(2),
+            SessionType::ShortBreak,
+            "ok",
+        );
+        rated_at(
+            &journal,
+            now - Duration::hours(1),
+            SessionType::Work,
+            "flow",
+        );
+        let stats = focus_stats(&config, &journal, now).unwrap();
+        assert_eq!(stats.streak, 2);
+        assert_eq!(stats.flow_today, 2);
+        assert!(!stats.goal_met());
+
+        // The `focused` rating ends the streak, yesterday's flow is not today's.
+        rated_at(
+            &journal,
+            now - Duration::minutes(30),
+            SessionType::Work,
+            "flow",
+        );
+        let stats = focus_stats(&config, &journal, now).unwrap();
+        assert_eq!(stats.streak, 3);
+        assert_eq!(stats.flow_today, 3);
+        assert!(stats.goal_met());
+        let config = ConfigCheck::new("daily_flow_goal: 4\n")
+            .into_result()
+            .unwrap();
+        assert!(!focus_stats(&config, &journal, now).unwrap().goal_met());
+        let no_goal = Config {
+            daily_flow_goal: Some(0),
+            ..Default::default()
+        };
+        assert!(!focus_stats(&no_goal, &journal, now).unwrap().goal_met());
+    }
+
+    #[test]
+    fn test_focus_rating_names() {
+        assert_eq!("flow".parse::<FocusRating>().unwrap(), Flow);
+        assert!("meh".parse::<FocusRating>().is_err());
+        assert_eq!(
+            serde_json::to_string(&Distracted).unwrap(),
+            "\"distracted\""
+        );
+        assert_eq!(Focused.as_str(), "focused");
+    }
+}
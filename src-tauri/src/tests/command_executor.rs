@@ -1,29 +1,64 @@
 // tests/command_executor.rs
-use std::process::Command;
-use std::thread::sleep;
-use std::time::Duration;
-
 #[cfg(test)]
 mod command_executor_tests {
-    use crate::CommandExecutor;
-    use std::path::PathBuf;
+    use crate::hooks::{HookContext, HookLog};
+    use crate::tests::{temp_dir, write_script};
+    use crate::{CommandExecutor, Session, Stopwatch};
 
     #[test]
     fn test_execute() {
-        let executor = CommandExecutor::new();
-        let script_path = PathBuf::from("./tests/test_script.sh");
-        executor.execute(
-            script_path.clone(),
-            "00:00:05".to_string(),
-            "Test App".to_string(),
+        let dir = temp_dir("executor");
+        let script_path = dir.join("on_log");
+        let output_path = dir.join("output.txt");
+        write_script(
+            &script_path,
+            &format!(
+                "#!/bin/sh\necho \"$2 $1 $MYTT_EVENT\" > {}\n",
+                output_path.display()
+            ),
         );
-        sleep(Duration::from_secs(6));
-        let output = Command::new("sh")
-            .arg("-c")
-            .arg("cat ./tests/test_output.txt")
-            .output()
-            .expect("Failed to execute command");
-        let output_str = String::from_utf8_lossy(&output.stdout);
-        assert_eq!(output_str.trim(), "Test App 00:00:05");
+
+        let mut context = HookContext::new("on_log", &Stopwatch::new(), &Session::new());
+        context.app_name = Some("Test App".to_string());
+        let log = dir.join("hooks.log");
+        let executor = CommandExecutor::with_log(HookLog::new(log.clone()));
+        executor
+            .execute(script_path, context, Default::default())
+            .unwrap();
+        executor.wait();
+
+        let output = std::fs::read_to_string(&output_path).unwrap();
+        assert_eq!(output.trim(), "Test App 00:00:00 on_log");
+        let runs = HookLog::new(log).recent(10).unwrap();
+        assert_eq!(runs.len(), 1);
+        assert!(runs[0].success);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_shutdown_kills_hooks_after_grace() {
+        let dir = temp_dir("shutdown");
+        let script_path = dir.join("on_stop");
+        write_script(&script_path, "#!/bin/sh\nsleep 30\n");
+
+        let log = dir.join("hooks.log");
+        let executor = CommandExecutor::with_log(HookLog::new(log.clone()));
+        let context = HookContext::new("on_stop", &Stopwatch::new(), &Session::new());
+        for _ in 0..3 {
+            executor
+                .execute(script_path.clone(), context.clone(), Default::default())
+                .unwrap();
+        }
+        std::thread::sleep(std::time::Duration::from_millis(200));
+
+        let started = std::time::Instant::now();
+        executor.shutdown(Some(std::time::Duration::from_millis(100)));
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+        // Only the hook that was running got to start.
+        assert_eq!(HookLog::new(log).recent(10).unwrap().len(), 1);
+        assert!(executor
+            .execute(script_path, context, Default::default())
+            .is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
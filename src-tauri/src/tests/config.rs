@@ -0,0 +1,297 @@
+// tests/config.rs
+#[cfg(test)]
+mod config_tests {
+    use crate::config::{
+        scripts_in, set_key, Bracket, ConfigCheck, Flowtime, Severity, CONFIG_TEMPLATE,
+    };
+    use serde_json::json;
+    use std::time::Duration;
+
+    #[test]
+    fn test_valid_config() {
+        let check = ConfigCheck::new("work_duration: 50\nshort_break_duration: 10\n");
+        assert!(check.valid);
+        assert!(check.diagnostics.is_empty());
+        let config = check.into_result().unwrap();
+        assert_eq!(config.work_duration, Some(50));
+        assert_eq!(config.short_break_duration, Some(10));
+    }
+
+    #[test]
+    fn test_empty_file_is_default() {
+        let config = ConfigCheck::new("\n").into_result().unwrap();
+        assert_eq!(config, Default::default());
+    }
+
+    #[test]
+    fn test_template_is_default() {
+        let check = ConfigCheck::new(CONFIG_TEMPLATE);
+        assert_eq!(check.warnings().count(), 0);
+        assert_eq!(check.into_result().unwrap(), Default::default());
+    }
+
+    #[test]
+    fn test_sessions_before_long_break_alias() {
+        let config = ConfigCheck::new("sessions_before_long_break: 3")
+            .into_result()
+            .unwrap();
+        assert_eq!(config.sessions_long_break, Some(3));
+    }
+
+    #[test]
+    fn test_type_error_has_location() {
+        let check = ConfigCheck::new("work_duration: 25\nlong_break_duration: soon\n");
+        assert!(!check.valid);
+        let error = &check.diagnostics[0];
+        assert_eq!(error.severity, Severity::Error);
+        assert_eq!(error.line, Some(2));
+        assert!(check.into_result().is_err());
+    }
+
+    #[test]
+    fn test_zero_duration_is_rejected() {
+        let check = ConfigCheck::new("theme: dark\nwork_duration: 0\n");
+        assert!(!check.valid);
+        let error = &check.diagnostics[0];
+        assert_eq!(error.line, Some(2));
+        assert_eq!(error.column, Some(1));
+        assert_eq!(
+            error.to_string(),
+            "config.yaml:2:1: error: work_duration must be greater than 0"
+        );
+    }
+
+    #[test]
+    fn test_sequence_errors() {
+        let check = ConfigCheck::new(
+            "sequence: ultradian
+sequences:
+  classic:
+    - { type: work, minutes: 50 }
+  empty: []
+  rest:
+    - { type: nap, minutes: 0 }
+  escape:
+    - { type: ../../bin, minutes: 5 }
+",
+        );
+        assert!(!check.valid);
+        let messages: Vec<_> = check
+            .diagnostics
+            .iter()
+            .map(|d| d.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "sequence: `ultradian` is not one of the sequences",
+                "sequences.classic is built in",
+                "sequences.empty must have at least one segment",
+                "sequences.escape: type `../../bin` must be a plain name",
+                "sequences.rest: minutes must be greater than 0",
+            ]
+        );
+        assert_eq!(check.diagnostics[0].line, Some(1));
+    }
+
+    #[test]
+    fn test_flowtime_breaks() {
+        let minutes = |n: u64| Duration::from_secs(n * 60);
+        let ratio = Flowtime::default();
+        assert_eq!(ratio.break_for(minutes(25)), 5 * 60);
+        assert_eq!(ratio.break_for(minutes(62)), 62 * 60 / 5);
+
+        let brackets = Flowtime {
+            ratio: Some(3),
+            brackets: Some(vec![
+                Bracket {
+                    up_to: Some(25),
+                    minutes: 5,
+                },
+                Bracket {
+                    up_to: Some(50),
+                    minutes: 8,
+                },
+                Bracket {
+                    up_to: Some(90),
+                    minutes: 10,
+                },
+            ]),
+        };
+        assert_eq!(brackets.break_for(minutes(10)), 5 * 60);
+        assert_eq!(brackets.break_for(minutes(50)), 8 * 60);
+        assert_eq!(brackets.break_for(minutes(51)), 10 * 60);
+        assert_eq!(brackets.break_for(minutes(180)), 10 * 60);
+    }
+
+    #[test]
+    fn test_flowtime_errors() {
+        let check = ConfigCheck::new(
+            "sequence: flowtime
+flowtime:
+  ratio: 0
+sequences:
+  flowtime:
+    - { type: work, minutes: 50 }
+",
+        );
+        let messages: Vec<_> = check
+            .diagnostics
+            .iter()
+            .map(|d| d.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "sequences.flowtime is built in",
+                "flowtime.ratio must be greater than 0",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_key_is_a_warning() {
+        let check = ConfigCheck::new("work_duration: 25\nwork_duraton: 30\n");
+        assert!(check.valid);
+        let warning = check.warnings().next().unwrap();
+        assert_eq!(warning.line, Some(2));
+        assert!(warning.message.contains("work_duraton"));
+        assert_eq!(check.into_result().unwrap().work_duration, Some(25));
+    }
+
+    #[test]
+    fn test_set_key_keeps_comments_and_order() {
+        let text = "# Pomodoro settings\ntheme: dark\nwork_duration: 25 # minutes\n\n# breaks\nshort_break_duration: 5\n";
+        let text = set_key(text, "work_duration", &json!(50)).unwrap();
+        assert_eq!(
+            text,
+            "# Pomodoro settings\ntheme: dark\nwork_duration: 50 # minutes\n\n# breaks\nshort_break_duration: 5\n"
+        );
+    }
+
+    #[test]
+    fn test_set_key_appends_missing_key() {
+        let text = set_key("theme: dark\n", "work_duration", &json!(30)).unwrap();
+        assert_eq!(text, "theme: dark\nwork_duration: 30\n");
+    }
+
+    #[test]
+    fn test_set_key_uses_existing_alias() {
+        let text = "sessions_before_long_break: 4\n";
+        let text = set_key(text, "sessions_long_break", &json!(3)).unwrap();
+        assert_eq!(text, "sessions_before_long_break: 3\n");
+    }
+
+    #[test]
+    fn test_set_key_null_removes_key() {
+        let text = "theme: dark\nwork_sound: bell\n";
+        let text = set_key(text, "work_sound", &json!(null)).unwrap();
+        assert_eq!(text, "theme: dark\n");
+    }
+
+    #[test]
+    fn test_set_key_quotes_strings() {
+        let text = set_key("", "theme", &json!("yes # no")).unwrap();
+        let config = ConfigCheck::new(&text).into_result().unwrap();
+        assert_eq!(config.theme.as_deref(), Some("yes # no"));
+    }
+
+    #[test]
+    fn test_set_key_merges_nested_keys() {
+        let text = "adaptive:\n  # bounds\n  min_duration: 10 # minutes\n  max_duration: 60\ntheme: dark\n";
+        let text = set_key(
+            text,
+            "adaptive",
+            &json!({ "min_duration": 15, "breaks": { "focused": 10 } }),
+        )
+        .unwrap();
+        assert_eq!(
+            text,
+            "adaptive:\n  # bounds\n  min_duration: 15 # minutes\n  max_duration: 60\n  breaks:\n    focused: 10\ntheme: dark\n"
+        );
+    }
+
+    #[test]
+    fn test_set_key_null_removes_nested_key() {
+        let text = "hooks:\n  on_log:\n    timeout: 5 # seconds\n    detached: true\n";
+        let text = set_key(text, "hooks", &json!({ "on_log": { "timeout": null } })).unwrap();
+        assert_eq!(text, "hooks:\n  on_log:\n    detached: true\n");
+    }
+
+    #[test]
+    fn test_set_key_merges_into_flow_mapping() {
+        let text = "hooks: { on_log: { timeout: 5 } } # slow\n";
+        let text = set_key(text, "hooks", &json!({ "on_log": { "detached": true } })).unwrap();
+        let config = ConfigCheck::new(&text).into_result().unwrap();
+        let on_log = &config.hooks.unwrap()["on_log"];
+        assert_eq!(on_log.timeout, Some(5));
+        assert_eq!(on_log.detached, Some(true));
+        assert!(text.starts_with("hooks: # slow\n"));
+    }
+
+    #[test]
+    fn test_set_key_replaces_sequences() {
+        let text = "sequences:\n  short:\n  - type: work\n    minutes: 10\nsequence: short\n";
+        let text = set_key(
+            text,
+            "sequences",
+            &json!({ "short": [{ "type": "work", "minutes": 15 }, { "type": "shortBreak", "minutes": 3 }] }),
+        )
+        .unwrap();
+        let config = ConfigCheck::new(&text).into_result().unwrap();
+        let short = &config.sequences.unwrap()["short"];
+        assert_eq!(short.len(), 2);
+        assert_eq!(short[0].minutes, 15);
+        assert!(text.ends_with("sequence: short\n"));
+    }
+
+    #[test]
+    fn test_set_key_rejects_unknown_key() {
+        assert!(set_key("", "colour", &json!("red")).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scripts_in_runs_parts_in_order() {
+        use crate::tests::{temp_dir, write_script};
+
+        let dir = temp_dir("scripts");
+        let parts = dir.join("on_log.d");
+        std::fs::create_dir_all(&parts).unwrap();
+        for name in [
+            "on_log",
+            "on_log.d/20-slack",
+            "on_log.d/10-obsidian",
+            "on_log.d/30-git-notes",
+            "on_log.d/.hidden",
+            "on_log.d/10-obsidian~",
+        ] {
+            write_script(&dir.join(name), "#!/bin/sh\n");
+        }
+        std::fs::write(parts.join("README"), "not a script").unwrap();
+
+        let names = |disabled: &[String]| -> Vec<String> {
+            scripts_in(&dir, "on_log", disabled)
+                .iter()
+                .map(|p| p.strip_prefix(&dir).unwrap().display().to_string())
+                .collect()
+        };
+        assert_eq!(
+            names(&[]),
+            [
+                "on_log",
+                "on_log.d/10-obsidian",
+                "on_log.d/20-slack",
+                "on_log.d/30-git-notes"
+            ]
+        );
+        assert_eq!(
+            names(&["20-slack".to_string(), "on_log".to_string()]),
+            ["on_log.d/10-obsidian", "on_log.d/30-git-notes"]
+        );
+        assert!(scripts_in(&dir, "on_start", &[]).is_empty());
+        std::fs::write(dir.join("on_start"), "#!/bin/sh\n").unwrap();
+        assert!(scripts_in(&dir, "on_start", &[]).is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,133 @@
+// tests/countdown.rs
+#[cfg(test)]
+mod countdown_tests {
+    use crate::clock::FakeClock;
+    use crate::config::Config;
+    use crate::countdown::{Countdown, Tick};
+    use crate::{Phase, Sequence, Session, SessionType, Status, Stopwatch, TimerState, Transition};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    const SECOND: Duration = Duration::from_secs(1);
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn test_tick_emits_once_per_second() {
+        let clock = FakeClock::default();
+        let mut stopwatch = Stopwatch::with_clock(Arc::new(clock.clone()));
+        let session = Session::new();
+        let mut countdown = Countdown::default();
+
+        assert!(countdown.tick(&Status::new(&stopwatch, &session)).emit);
+        clock.advance(SECOND);
+        assert!(!countdown.tick(&Status::new(&stopwatch, &session)).emit);
+
+        stopwatch.start();
+        assert!(countdown.tick(&Status::new(&stopwatch, &session)).emit);
+        clock.advance(Duration::from_millis(250));
+        assert!(countdown.tick(&Status::new(&stopwatch, &session)).emit);
+        clock.advance(Duration::from_millis(250));
+        assert!(!countdown.tick(&Status::new(&stopwatch, &session)).emit);
+        clock.advance(Duration::from_millis(750));
+        assert!(countdown.tick(&Status::new(&stopwatch, &session)).emit);
+    }
+
+    #[test]
+    fn test_session_completes_on_time() {
+        let clock = FakeClock::default();
+        let mut stopwatch = Stopwatch::with_clock(Arc::new(clock.clone()));
+        let mut session = Session::new();
+        session.set_target_duration(90);
+        let mut countdown = Countdown::default();
+
+        stopwatch.start();
+        clock.advance(89 * SECOND);
+        let status = Status::new(&stopwatch, &session);
+        assert_eq!(status.remaining_ms, 1000);
+        assert!(!countdown.tick(&status).complete);
+
+        stopwatch.pause();
+        clock.advance(10 * MINUTE);
+        assert!(!countdown.tick(&Status::new(&stopwatch, &session)).complete);
+
+        stopwatch.resume();
+        clock.advance(SECOND);
+        let status = Status::new(&stopwatch, &session);
+        assert_eq!(
+            countdown.tick(&status),
+            Tick {
+                emit: true,
+                complete: true
+            }
+        );
+    }
+
+    #[test]
+    fn test_flowtime_work_does_not_complete() {
+        let clock = FakeClock::default();
+        let mut stopwatch = Stopwatch::with_clock(Arc::new(clock.clone()));
+        let mut session = Session::with_config(&Config {
+            sequence: Some(Sequence::FLOWTIME.to_string()),
+            ..Default::default()
+        });
+        let mut countdown = Countdown::default();
+
+        session.apply(Transition::Start).unwrap();
+        stopwatch.start();
+        clock.advance(180 * MINUTE);
+        let status = Status::new(&stopwatch, &session);
+        assert!(status.open_ended);
+        assert_eq!(status.remaining_ms, 0);
+        assert!(!countdown.tick(&status).complete);
+        clock.advance(SECOND);
+        assert!(countdown.tick(&Status::new(&stopwatch, &session)).emit);
+
+        // The break it earned, a fifth of the work, counts down like any other.
+        let elapsed = Duration::from_millis(stopwatch.elapsed() as u64);
+        session.apply_timed(Transition::Stop, elapsed).unwrap();
+        stopwatch.stop();
+        session.apply(Transition::Start).unwrap();
+        stopwatch.start();
+        let status = Status::new(&stopwatch, &session);
+        assert!(!status.open_ended);
+        assert_eq!(status.remaining_ms, 2160 * 1000);
+        clock.advance(36 * MINUTE);
+        assert!(countdown.tick(&Status::new(&stopwatch, &session)).complete);
+    }
+
+    #[test]
+    fn test_long_break_cadence() {
+        let clock = FakeClock::default();
+        let mut stopwatch = Stopwatch::with_clock(Arc::new(clock.clone()));
+        let mut session = Session::new();
+        let mut countdown = Countdown::default();
+        let mut types = Vec::new();
+
+        // Run each session to completion, the way the app moves on from one.
+        let step = |stopwatch: &mut Stopwatch, session: &mut Session, transition| {
+            let event = session.apply(transition).unwrap();
+            stopwatch.follow(&event.to);
+            event.to
+        };
+        for _ in 0..8 {
+            types.push(session.current_session());
+            step(&mut stopwatch, &mut session, Transition::Start);
+            clock.advance(Duration::from_secs(session.target_duration()));
+            assert!(countdown.tick(&Status::new(&stopwatch, &session)).complete);
+            if step(&mut stopwatch, &mut session, Transition::Complete) == Phase::AwaitingRating {
+                assert_eq!(stopwatch.state(), TimerState::Paused);
+                step(&mut stopwatch, &mut session, Transition::Log);
+            }
+            assert_eq!(stopwatch.state(), TimerState::Stopped);
+            assert!(!countdown.tick(&Status::new(&stopwatch, &session)).complete);
+        }
+
+        use SessionType::*;
+        assert_eq!(
+            types,
+            vec![Work, ShortBreak, Work, ShortBreak, Work, ShortBreak, Work, LongBreak]
+        );
+        assert_eq!(session.work_sessions, 4);
+        assert_eq!(session.long_break_sessions, 1);
+    }
+}
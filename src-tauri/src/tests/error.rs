@@ -0,0 +1,35 @@
+// tests/error.rs
+#[cfg(test)]
+mod error_tests {
+    use crate::error::MyttError;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_serialize_for_frontend() {
+        let error = MyttError::Script("on_log exited with status 1".to_string());
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(json["kind"], "script");
+        assert_eq!(json["message"], "script: on_log exited with status 1");
+    }
+
+    #[test]
+    fn test_poisoned_lock_is_state_error() {
+        let mutex = Arc::new(Mutex::new(0));
+        let poisoned = Arc::clone(&mutex);
+        let _ = std::thread::spawn(move || {
+            let _guard = poisoned.lock().unwrap();
+            panic!("poison the lock");
+        })
+        .join();
+        let error: MyttError = mutex.lock().unwrap_err().into();
+        assert_eq!(error.kind(), "state");
+    }
+
+    #[test]
+    fn test_yaml_error_is_config_error() {
+        let error: MyttError = serde_yaml::from_str::<u64>("[not, a, number]")
+            .unwrap_err()
+            .into();
+        assert_eq!(error.kind(), "config");
+    }
+}
@@ -0,0 +1,57 @@
+// tests/frontmost.rs
+#[cfg(test)]
+mod frontmost_tests {
+    use crate::frontmost::{parse_hyprland_active_window, parse_sway_tree, FrontmostApp};
+    use serde_json::json;
+
+    fn app(name: &str, title: &str) -> Option<FrontmostApp> {
+        Some(FrontmostApp {
+            name: name.to_string(),
+            title: Some(title.to_string()),
+        })
+    }
+
+    #[test]
+    fn test_hyprland_active_window() {
+        let reply = r#"{"address":"0x1","class":"kitty","title":"nvim main.rs","pid":42}"#;
+        assert_eq!(parse_hyprland_active_window(reply), app("kitty", "nvim main.rs"));
+        assert_eq!(parse_hyprland_active_window("{}"), None);
+        assert_eq!(parse_hyprland_active_window("Invalid"), None);
+    }
+
+    #[test]
+    fn test_sway_native_window() {
+        let tree = json!({
+            "focused": false,
+            "nodes": [{
+                "focused": false,
+                "nodes": [
+                    { "focused": false, "app_id": "foot", "name": "shell", "nodes": [] },
+                    { "focused": true, "app_id": "firefox", "name": "Docs", "nodes": [] }
+                ]
+            }]
+        });
+        assert_eq!(parse_sway_tree(&tree), app("firefox", "Docs"));
+    }
+
+    #[test]
+    fn test_sway_xwayland_floating_window() {
+        let tree = json!({
+            "focused": false,
+            "nodes": [],
+            "floating_nodes": [{
+                "focused": true,
+                "app_id": null,
+                "name": "Slack",
+                "window_properties": { "class": "Slack" }
+            }]
+        });
+        assert_eq!(parse_sway_tree(&tree), app("Slack", "Slack"));
+    }
+
+    #[test]
+    fn test_sway_nothing_focused() {
+        let tree = json!({ "focused": false, "nodes": [] });
+        assert_eq!(parse_sway_tree(&tree), None);
+    }
+}
@@ -0,0 +1,258 @@
+// tests/hooks.rs
+#[cfg(test)]
+mod hooks_tests {
+    use crate::adaptive::{FocusRating, FocusStats};
+    use crate::config::{Config, HookSettings};
+    use crate::frontmost::FrontmostApp;
+    use crate::hooks::{
+        inventory, HookContext, HookLog, HookResponse, HookRun, RunningHooks, EVENTS,
+    };
+    use crate::tests::{temp_dir, write_script};
+    use crate::{Session, Stopwatch};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_env_sets_every_variable() {
+        let mut session = Session::new();
+        session.work_sessions = 3;
+        let context = HookContext::new("on_start", &Stopwatch::new(), &session);
+        let env: HashMap<_, _> = context.env().into_iter().collect();
+        assert_eq!(env["MYTT_EVENT"], "on_start");
+        assert_eq!(env["MYTT_SESSION_TYPE"], "work");
+        assert_eq!(env["MYTT_PLANNED_SECONDS"], "1500");
+        assert_eq!(env["MYTT_WORK_SESSIONS"], "3");
+        assert_eq!(env["MYTT_STARTED_AT"], "");
+        assert_eq!(env["MYTT_FOCUS_RATING"], "");
+        assert_eq!(env["MYTT_PHASE"], "idle");
+        assert_eq!(env["MYTT_TRANSITION"], "");
+        assert_eq!(env["MYTT_FLOW_STREAK"], "");
+        assert_eq!(env.len(), 16);
+    }
+
+    #[test]
+    fn test_env_has_rating() {
+        let stats = FocusStats {
+            streak: 2,
+            flow_today: 1,
+            daily_goal: 3,
+        };
+        let context = HookContext::new("on_rate", &Stopwatch::new(), &Session::new()).with_rating(
+            FocusRating::Flow,
+            Some("deep work".to_string()),
+            stats,
+        );
+        let env: HashMap<_, _> = context.env().into_iter().collect();
+        assert_eq!(env["MYTT_FOCUS_RATING"], "flow");
+        assert_eq!(env["MYTT_NOTE"], "deep work");
+        assert_eq!(env["MYTT_FLOW_STREAK"], "2");
+        assert_eq!(env["MYTT_FLOW_TODAY"], "1");
+        assert_eq!(env["MYTT_DAILY_FLOW_GOAL"], "3");
+    }
+
+    #[test]
+    fn test_only_ending_events_have_end_time() {
+        let stopwatch = Stopwatch::new();
+        let session = Session::new();
+        assert!(HookContext::new("on_start", &stopwatch, &session)
+            .ended_at
+            .is_none());
+        assert!(HookContext::new("on_log", &stopwatch, &session)
+            .ended_at
+            .is_some());
+    }
+
+    #[test]
+    fn test_with_frontmost() {
+        let context = HookContext::new("on_blur", &Stopwatch::new(), &Session::new())
+            .with_frontmost(Some(FrontmostApp {
+                name: "firefox".to_string(),
+                title: Some("Docs".to_string()),
+            }));
+        assert_eq!(context.app_name.as_deref(), Some("firefox"));
+        assert_eq!(context.window_title.as_deref(), Some("Docs"));
+    }
+
+    #[test]
+    fn test_run_passes_json_on_stdin() {
+        let dir = temp_dir("hooks");
+        let script = dir.join("on_stop");
+        write_script(&script, "#!/bin/sh\ncat\n");
+
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.start();
+        stopwatch.lap(Some("reviewing PR".to_string()));
+        let context = HookContext::new("on_stop", &stopwatch, &Session::new());
+        assert_eq!(context.laps.len(), 1);
+        let output = context
+            .run(&script, None, &RunningHooks::default())
+            .unwrap()
+            .output;
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(output.status.success());
+        let received: HookContext = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(received, context);
+    }
+
+    #[test]
+    fn test_failed_run_is_recorded() {
+        let dir = temp_dir("hook-runs");
+        let script = dir.join("on_log");
+        write_script(&script, "#!/bin/sh\necho vault not found >&2\nexit 3\n");
+
+        let context = HookContext::new("on_log", &Stopwatch::new(), &Session::new());
+        let running = RunningHooks::default();
+        let run = HookRun::record(&script, &context, None, &running);
+        assert!(!run.success);
+        assert_eq!(run.exit_code, Some(3));
+        assert_eq!(run.stderr, "vault not found\n");
+        assert_eq!(
+            run.failure().as_deref(),
+            Some("on_log exited with status 3: vault not found")
+        );
+
+        let missing = HookRun::record(&dir.join("missing"), &context, None, &running);
+        assert!(missing.error.is_some());
+        assert!(missing.failure().unwrap().contains("could not be started"));
+
+        let log = HookLog::new(dir.join("hooks.log"));
+        log.append(&run).unwrap();
+        log.append(&missing).unwrap();
+        let runs = log.recent(10).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(runs, vec![missing, run]);
+    }
+
+    #[test]
+    fn test_response_is_last_json_line() {
+        let response =
+            HookResponse::parse("synced\n{\"note\": \"Reviewed PR\", \"tags\": [\"review\"]}\n\n")
+                .unwrap();
+        assert_eq!(response.note.as_deref(), Some("Reviewed PR"));
+        assert_eq!(response.tags, vec!["review"]);
+        assert!(!response.veto);
+        assert!(HookResponse::parse("{\"note\": \"early\"}\ndone\n").is_none());
+        assert!(HookResponse::parse("{not json}").is_none());
+        assert!(HookResponse::parse("").is_none());
+    }
+
+    #[test]
+    fn test_merge_responses() {
+        let mut merged = HookResponse {
+            note: Some("first".to_string()),
+            tags: vec!["a".to_string()],
+            next_duration: Some(300),
+            ..Default::default()
+        };
+        merged.merge(HookResponse {
+            note: Some("second".to_string()),
+            tags: vec!["a".to_string(), "b".to_string()],
+            veto: true,
+            reason: Some("finish the review".to_string()),
+            notify: Some("shown already".to_string()),
+            ..Default::default()
+        });
+        merged.merge(HookResponse {
+            next_duration: Some(600),
+            veto: true,
+            reason: Some("ignored".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(merged.note.as_deref(), Some("first\nsecond"));
+        assert_eq!(merged.tags, vec!["a", "b"]);
+        assert_eq!(merged.next_duration, Some(600));
+        assert!(merged.veto);
+        assert_eq!(merged.reason.as_deref(), Some("finish the review"));
+        assert_eq!(merged.notify, None);
+    }
+
+    #[test]
+    fn test_timeout_kills_process_group() {
+        let dir = temp_dir("hook-timeout");
+        let script = dir.join("on_start");
+        // The background sleep holds stdout open after the script is killed.
+        write_script(&script, "#!/bin/sh\nsleep 30 &\nsleep 30\n");
+
+        let context = HookContext::new("on_start", &Stopwatch::new(), &Session::new());
+        let started = std::time::Instant::now();
+        let run = HookRun::record(
+            &script,
+            &context,
+            Some(std::time::Duration::from_millis(200)),
+            &RunningHooks::default(),
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+        assert!(run.timed_out);
+        assert!(!run.success);
+        assert!(run.failure().unwrap().contains("timed out"));
+    }
+
+    #[test]
+    fn test_timeout_covers_unread_stdin() {
+        let dir = temp_dir("hook-stdin");
+        let script = dir.join("on_log");
+        write_script(&script, "#!/bin/sh\nsleep 30\n");
+
+        // More than a pipe holds, and the script never reads any of it.
+        let mut context = HookContext::new("on_log", &Stopwatch::new(), &Session::new());
+        context.note = Some("x".repeat(100_000));
+        let started = std::time::Instant::now();
+        let run = HookRun::record(
+            &script,
+            &context,
+            Some(std::time::Duration::from_millis(200)),
+            &RunningHooks::default(),
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+        assert!(run.timed_out);
+    }
+
+    #[test]
+    fn test_inventory() {
+        let dir = temp_dir("inventory");
+        std::fs::create_dir_all(dir.join("on_log.d")).unwrap();
+        let script = dir.join("on_log");
+        write_script(&script, "#!/bin/sh\n");
+        std::fs::write(dir.join("on_log.d/10-notes"), "#!/bin/sh\n").unwrap();
+        std::fs::create_dir_all(dir.join("longBreak")).unwrap();
+        write_script(&dir.join("longBreak/on_log"), "#!/bin/sh\n");
+
+        let log = HookLog::new(dir.join("hooks.log"));
+        let context = HookContext::new("on_log", &Stopwatch::new(), &Session::new());
+        let run = HookRun::record(&script, &context, None, &RunningHooks::default());
+        log.append(&run).unwrap();
+
+        let config = Config {
+            required_hooks: Some(vec!["on_log".to_string()]),
+            hooks: Some(
+                [(
+                    "on_log".to_string(),
+                    HookSettings {
+                        disabled: Some(vec!["10-notes".to_string()]),
+                        ..Default::default()
+                    },
+                )]
+                .into(),
+            ),
+            ..Default::default()
+        };
+        let hooks = inventory(&dir, &config, &log).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(hooks.len(), EVENTS.len());
+        let on_log = hooks.iter().find(|h| h.event == "on_log").unwrap();
+        assert!(on_log.required);
+        assert_eq!(on_log.scripts.len(), 3);
+        assert!(on_log.scripts[0].executable && on_log.scripts[0].enabled);
+        assert_eq!(on_log.scripts[0].last_run, Some(run));
+        assert!(!on_log.scripts[1].executable && !on_log.scripts[1].enabled);
+        assert_eq!(on_log.scripts[1].last_run, None);
+        assert_eq!(on_log.scripts[2].path, dir.join("longBreak/on_log"));
+        let on_start = hooks.iter().find(|h| h.event == "on_start").unwrap();
+        assert!(!on_start.required && on_start.scripts.is_empty());
+    }
+}
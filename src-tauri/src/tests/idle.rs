@@ -0,0 +1,58 @@
+// tests/idle.rs
+#[cfg(test)]
+mod idle_tests {
+    use crate::config::Config;
+    use crate::idle::{change, parse_logind_idle, Change, IdlePeriod};
+    use chrono::Local;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn test_change() {
+        let threshold = 5 * MINUTE;
+        assert_eq!(
+            change(true, false, 6 * MINUTE, threshold),
+            Some(Change::Left(6 * MINUTE))
+        );
+        assert_eq!(change(true, false, MINUTE, threshold), None);
+        // Breaks and paused sessions are left alone.
+        assert_eq!(change(false, false, 6 * MINUTE, threshold), None);
+        assert_eq!(change(false, true, 7 * MINUTE, threshold), None);
+        assert_eq!(
+            change(false, true, Duration::from_secs(2), threshold),
+            Some(Change::Returned(Duration::from_secs(2)))
+        );
+    }
+
+    #[test]
+    fn test_parse_logind_idle() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_600);
+        let idle = "IdleHint=yes\nIdleSinceHint=1700000000000000\n";
+        assert_eq!(parse_logind_idle(idle, now), Some(10 * MINUTE));
+        let active = "IdleHint=no\nIdleSinceHint=0\n";
+        assert_eq!(parse_logind_idle(active, now), Some(Duration::ZERO));
+        assert_eq!(parse_logind_idle("", SystemTime::now()), None);
+    }
+
+    #[test]
+    fn test_idle_period_duration() {
+        let mut period = IdlePeriod::since(10 * MINUTE);
+        assert!(!period.is_over());
+        assert!(period.duration() >= 10 * MINUTE);
+        period.ended_at = Some(period.started_at + chrono::Duration::minutes(4));
+        assert!(period.is_over());
+        assert_eq!(period.duration(), 4 * MINUTE);
+        assert!(period.ended_at.unwrap() < Local::now());
+    }
+
+    #[test]
+    fn test_idle_threshold() {
+        assert_eq!(Config::default().idle_threshold(), Some(5 * MINUTE));
+        let config = Config {
+            idle_threshold: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(config.idle_threshold(), None);
+    }
+}
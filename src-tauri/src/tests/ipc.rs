@@ -0,0 +1,122 @@
+// tests/ipc.rs
+#[cfg(test)]
+mod ipc_tests {
+    use crate::adaptive::FocusRating;
+    use crate::ipc::{publish, serve, Client, Request, Response};
+    use crate::tests::temp_path;
+    use crate::{Phase, SessionType, Status, TimerState};
+    use std::path::PathBuf;
+    use std::sync::{Mutex, MutexGuard};
+    use std::time::{Duration, Instant};
+
+    /// Held by tests that publish, since every subscriber of the process gets
+    /// what any of them publishes.
+    static PUBLISHING: Mutex<()> = Mutex::new(());
+
+    fn publishing() -> MutexGuard<'static, ()> {
+        PUBLISHING.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn status(state: TimerState) -> Status {
+        Status {
+            session_type: SessionType::Work,
+            phase: Phase::Running(SessionType::Work),
+            state,
+            elapsed_ms: 1000,
+            remaining_ms: 1499000,
+            open_ended: false,
+        }
+    }
+
+    fn socket_path(name: &str) -> PathBuf {
+        temp_path(&format!("{}.sock", name))
+    }
+
+    fn fake_handler(request: Request) -> Response {
+        match request {
+            Request::GetTime => Response::Time("00:00:01".to_string()),
+            Request::Pause => Response::Status(status(TimerState::Paused)),
+            _ => Response::Status(status(TimerState::Running)),
+        }
+    }
+
+    #[test]
+    fn test_request_wire_format() {
+        let json = serde_json::to_string(&Request::GetTime).unwrap();
+        assert_eq!(json, r#"{"cmd":"get_time"}"#);
+        let request: Request = serde_json::from_str(r#"{"cmd":"start"}"#).unwrap();
+        assert_eq!(request, Request::Start);
+        let request: Request = serde_json::from_str(r#"{"cmd":"rate","rating":"flow"}"#).unwrap();
+        assert_eq!(
+            request,
+            Request::Rate {
+                rating: FocusRating::Flow,
+                note: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_client_round_trip() {
+        let path = socket_path("round-trip");
+        serve(path.clone(), fake_handler).unwrap();
+        let mut client = Client::connect(&path).unwrap().unwrap();
+        assert_eq!(
+            client.send(Request::GetTime).unwrap(),
+            Response::Time("00:00:01".to_string())
+        );
+        assert_eq!(
+            client.send(Request::Pause).unwrap(),
+            Response::Status(status(TimerState::Paused))
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_subscribe_receives_published_state() {
+        let _publishing = publishing();
+        let path = socket_path("subscribe");
+        serve(path.clone(), fake_handler).unwrap();
+        let mut client = Client::connect(&path).unwrap().unwrap();
+        assert_eq!(
+            client.send(Request::Subscribe).unwrap(),
+            Response::Subscribed
+        );
+        publish(&status(TimerState::Stopped));
+        assert_eq!(
+            client.recv().unwrap(),
+            Some(Response::StateChanged(status(TimerState::Stopped)))
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_stalled_subscriber_is_dropped() {
+        let _publishing = publishing();
+        let path = socket_path("stalled");
+        serve(path.clone(), fake_handler).unwrap();
+        let mut client = Client::connect(&path).unwrap().unwrap();
+        client.send(Request::Subscribe).unwrap();
+        // The client never reads again, so its socket buffer soon fills up.
+        let started = Instant::now();
+        for _ in 0..20_000 {
+            publish(&status(TimerState::Stopped));
+        }
+        assert!(started.elapsed() < Duration::from_secs(10));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_connect_without_server() {
+        let path = socket_path("missing");
+        assert!(Client::connect(&path).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_serve_refuses_live_socket() {
+        let path = socket_path("live");
+        serve(path.clone(), fake_handler).unwrap();
+        assert!(serve(path.clone(), fake_handler).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,144 @@
+// tests/journal.rs
+#[cfg(test)]
+mod journal_tests {
+    use crate::adaptive::FocusRating;
+    use crate::journal::{Journal, NewJournalEntry};
+    use crate::tests::temp_path;
+    use crate::SessionType;
+    use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDate};
+
+    fn entry(minutes_ago: i64, session_type: SessionType) -> NewJournalEntry {
+        let ended_at = Local::now() - Duration::minutes(minutes_ago);
+        NewJournalEntry {
+            started_at: ended_at - Duration::minutes(25),
+            ended_at,
+            is_break: session_type.is_break(),
+            session_type,
+            paused_ms: 1500,
+            suspended_ms: 500,
+            app_name: Some("Neovim".to_string()),
+            focus_rating: None,
+            note: Some("writing tests".to_string()),
+            tags: vec!["deep".to_string(), "rust".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_insert_and_get() {
+        let journal = Journal::open_in_memory().unwrap();
+        let id = journal.insert(&entry(0, SessionType::Work)).unwrap();
+        let stored = journal.get(id).unwrap().unwrap();
+        assert_eq!(stored.id, id);
+        assert_eq!(stored.session_type, SessionType::Work);
+        assert_eq!(stored.paused_ms, 1500);
+        assert_eq!(stored.suspended_ms, 500);
+        assert_eq!(stored.app_name.as_deref(), Some("Neovim"));
+        assert_eq!(stored.note.as_deref(), Some("writing tests"));
+        assert_eq!(stored.tags, vec!["deep", "rust"]);
+        assert!(journal.get(id + 1).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_list_most_recent_first() {
+        let journal = Journal::open_in_memory().unwrap();
+        let older = journal.insert(&entry(60, SessionType::Work)).unwrap();
+        let newer = journal.insert(&entry(0, SessionType::ShortBreak)).unwrap();
+        let entries = journal.list(10, 0).unwrap();
+        let ids: Vec<i64> = entries.iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![newer, older]);
+        assert_eq!(journal.list(1, 1).unwrap()[0].id, older);
+    }
+
+    #[test]
+    fn test_delete() {
+        let journal = Journal::open_in_memory().unwrap();
+        let id = journal.insert(&entry(0, SessionType::LongBreak)).unwrap();
+        assert!(journal.delete(id).unwrap());
+        assert!(!journal.delete(id).unwrap());
+        assert!(journal.list(10, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rate_latest_work_session() {
+        let journal = Journal::open_in_memory().unwrap();
+        assert_eq!(journal.rate_latest(FocusRating::Flow, None).unwrap(), None);
+        let work = journal.insert(&entry(30, SessionType::Work)).unwrap();
+        journal.insert(&entry(0, SessionType::ShortBreak)).unwrap();
+
+        assert_eq!(
+            journal.rate_latest(FocusRating::Ok, None).unwrap(),
+            Some(work)
+        );
+        let stored = journal.get(work).unwrap().unwrap();
+        assert_eq!(stored.focus_rating.as_deref(), Some("ok"));
+        assert_eq!(stored.note.as_deref(), Some("writing tests"));
+
+        // Rating again replaces the rating, and a note replaces the note.
+        journal
+            .rate_latest(FocusRating::Flow, Some("shipped it"))
+            .unwrap();
+        let stored = journal.get(work).unwrap().unwrap();
+        assert_eq!(stored.focus_rating.as_deref(), Some("flow"));
+        assert_eq!(stored.note.as_deref(), Some("shipped it"));
+    }
+
+    #[test]
+    fn test_custom_break_is_not_work() {
+        let journal = Journal::open_in_memory().unwrap();
+        let work = journal.insert(&entry(30, SessionType::Work)).unwrap();
+        journal
+            .insert(&NewJournalEntry {
+                is_break: true,
+                ..entry(0, SessionType::Custom("walk".to_string()))
+            })
+            .unwrap();
+        assert_eq!(
+            journal.rate_latest(FocusRating::Flow, None).unwrap(),
+            Some(work)
+        );
+    }
+
+    #[test]
+    fn test_order_across_offset_change() {
+        // The clocks went back an hour between these two: the later session
+        // ended at an earlier local time.
+        let at = |hour: u32, minute: u32, offset_hours: i32| {
+            let offset = FixedOffset::west_opt(offset_hours * 3600).unwrap();
+            let local = NaiveDate::from_ymd_opt(2024, 11, 3)
+                .unwrap()
+                .and_hms_opt(hour, minute, 0)
+                .unwrap();
+            DateTime::<Local>::from_naive_utc_and_offset(local - offset, offset)
+        };
+        let rated = |ended_at| NewJournalEntry {
+            ended_at,
+            focus_rating: Some("flow".to_string()),
+            ..entry(0, SessionType::Work)
+        };
+        let journal = Journal::open_in_memory().unwrap();
+        // Logged out of order, so the id cannot settle it either.
+        let later = journal.insert(&rated(at(1, 10, 5))).unwrap();
+        journal.insert(&rated(at(1, 30, 4))).unwrap();
+
+        assert_eq!(
+            journal.rate_latest(FocusRating::Ok, None).unwrap(),
+            Some(later)
+        );
+        assert_eq!(journal.flow_sessions_since(at(1, 0, 5)).unwrap(), 0);
+        assert_eq!(journal.flow_streak().unwrap(), 0);
+        assert_eq!(journal.recent_ratings(1).unwrap(), [FocusRating::Ok]);
+    }
+
+    #[test]
+    fn test_reopen_keeps_entries() {
+        let path = temp_path("journal.sqlite3");
+        let _ = std::fs::remove_file(&path);
+        let id = Journal::open(&path)
+            .unwrap()
+            .insert(&entry(0, SessionType::Work))
+            .unwrap();
+        let journal = Journal::open(&path).unwrap();
+        assert!(journal.get(id).unwrap().is_some());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
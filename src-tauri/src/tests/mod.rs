@@ -0,0 +1,42 @@
+//! Unit tests, one file per module under test, plus the fixtures they share.
+use std::path::{Path, PathBuf};
+
+mod adaptive;
+#[cfg(unix)]
+mod command_executor;
+mod config;
+mod countdown;
+mod error;
+mod frontmost;
+#[cfg(unix)]
+mod hooks;
+mod idle;
+#[cfg(unix)]
+mod ipc;
+mod journal;
+mod scripting;
+mod session;
+mod stopwatch;
+
+/// A path under the system temp directory that no other test or test run
+/// uses, named after `name`.
+pub fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("mytt-{}-{}", name, std::process::id()))
+}
+
+/// Creates an empty directory at `temp_path(name)`.
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = temp_path(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Writes `contents` to `path` and makes it executable.
+#[cfg(unix)]
+pub fn write_script(path: &Path, contents: &str) {
+    use std::os::unix::fs::PermissionsExt;
+
+    std::fs::write(path, contents).unwrap();
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755)).unwrap();
+}
@@ -0,0 +1,144 @@
+// tests/scripting.rs
+#[cfg(test)]
+mod scripting_tests {
+    use crate::config::scripts_in;
+    use crate::hooks::{HookContext, HookRun, RunningHooks};
+    use crate::scripting::{is_embedded, resolve_in};
+    use crate::tests::temp_dir;
+    use crate::{Session, Stopwatch};
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    fn context() -> HookContext {
+        HookContext::new("on_log", &Stopwatch::new(), &Session::new())
+    }
+
+    #[test]
+    fn test_script_sees_context_and_responds() {
+        let dir = temp_dir("rhai-respond");
+        let script = dir.join("on_log.rhai");
+        std::fs::write(
+            &script,
+            r#"
+                print(`logging ${ctx.session_type}`);
+                #{ note: ctx.event, tags: ["rhai"], next_duration: 900 }
+            "#,
+        )
+        .unwrap();
+
+        let run = HookRun::record(&script, &context(), None, &RunningHooks::default());
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(run.success, "{:?}", run);
+        assert_eq!(run.exit_code, Some(0));
+        assert_eq!(run.stdout, "logging work\n");
+        let response = run.response.unwrap();
+        assert_eq!(response.note.as_deref(), Some("on_log"));
+        assert_eq!(response.tags, vec!["rhai"]);
+        assert_eq!(response.next_duration, Some(900));
+    }
+
+    #[test]
+    fn test_script_error_is_recorded() {
+        let dir = temp_dir("rhai-error");
+        let script = dir.join("on_log.rhai");
+        std::fs::write(&script, "throw \"vault not found\";").unwrap();
+
+        let run = HookRun::record(&script, &context(), None, &RunningHooks::default());
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(!run.success);
+        assert_eq!(run.exit_code, Some(1));
+        assert!(run.failure().unwrap().contains("vault not found"));
+    }
+
+    #[test]
+    fn test_script_timeout() {
+        let dir = temp_dir("rhai-timeout");
+        let script = dir.join("on_log.rhai");
+        std::fs::write(&script, "loop {}").unwrap();
+
+        let timeout = Some(Duration::from_millis(100));
+        let run = HookRun::record(&script, &context(), timeout, &RunningHooks::default());
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(!run.success);
+        assert!(run.timed_out);
+        assert_eq!(run.exit_code, None);
+    }
+
+    #[test]
+    fn test_kill_all_stops_script() {
+        let dir = temp_dir("rhai-kill");
+        let script = dir.join("on_log.rhai");
+        std::fs::write(&script, "loop {}").unwrap();
+
+        let running = RunningHooks::default();
+        let run = std::thread::scope(|scope| {
+            let run = scope.spawn(|| HookRun::record(&script, &context(), None, &running));
+            std::thread::sleep(Duration::from_millis(100));
+            running.kill_all();
+            run.join().unwrap()
+        });
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(!run.success);
+        assert!(!run.timed_out);
+    }
+
+    #[test]
+    fn test_writes_stay_in_home() {
+        let home = Path::new("/home/me");
+        assert_eq!(
+            resolve_in(home, "~/notes/log.md"),
+            Some(PathBuf::from("/home/me/notes/log.md"))
+        );
+        assert_eq!(
+            resolve_in(home, "/home/me/log.md"),
+            Some(PathBuf::from("/home/me/log.md"))
+        );
+        assert_eq!(resolve_in(home, "/etc/passwd"), None);
+        assert_eq!(resolve_in(home, "~/../other/log.md"), None);
+        assert_eq!(resolve_in(home, "notes/log.md"), None);
+
+        let dir = temp_dir("rhai-write");
+        let script = dir.join("on_log.rhai");
+        std::fs::write(&script, r#"write_file("/etc/mytt-test", "no");"#).unwrap();
+        let run = HookRun::record(&script, &context(), None, &RunningHooks::default());
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(run.failure().unwrap().contains("outside the home directory"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_writes_do_not_follow_symlinks_out_of_home() {
+        let home = temp_dir("rhai-home");
+        let outside = temp_dir("rhai-outside");
+        std::os::unix::fs::symlink(&outside, home.join("link")).unwrap();
+        std::os::unix::fs::symlink(outside.join("log.md"), home.join("log.md")).unwrap();
+        std::fs::create_dir(home.join("notes")).unwrap();
+
+        assert_eq!(resolve_in(&home, "~/link/log.md"), None);
+        assert_eq!(resolve_in(&home, "~/link/new/log.md"), None);
+        assert_eq!(resolve_in(&home, "~/log.md"), None);
+        assert_eq!(
+            resolve_in(&home, "~/notes/log.md"),
+            Some(home.join("notes/log.md"))
+        );
+        std::fs::remove_dir_all(&home).unwrap();
+        std::fs::remove_dir_all(&outside).unwrap();
+    }
+
+    #[test]
+    fn test_rhai_scripts_run_without_exec_bit() {
+        let dir = temp_dir("rhai-lookup");
+        std::fs::create_dir_all(dir.join("on_log.d")).unwrap();
+        std::fs::write(dir.join("on_log.rhai"), "").unwrap();
+        std::fs::write(dir.join("on_log.d/10-sync.rhai"), "").unwrap();
+        std::fs::write(dir.join("on_log.d/20-notes"), "").unwrap();
+
+        let scripts = scripts_in(&dir, "on_log", &[]);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            scripts,
+            vec![dir.join("on_log.rhai"), dir.join("on_log.d/10-sync.rhai")]
+        );
+        assert!(scripts.iter().all(|path| is_embedded(path)));
+    }
+}
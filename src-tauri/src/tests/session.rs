@@ -1,19 +1,26 @@
-
 // tests/session.rs
 #[cfg(test)]
 mod session_tests {
-    use crate::Session;
-    use crate::SessionType;
+    use crate::config::{Config, ConfigCheck};
+    use crate::{Phase, Sequence, Session, SessionType, Transition};
+    use std::time::Duration;
+
+    /// Runs `session` through one whole session and logs it.
+    fn finish(session: &mut Session) {
+        session.apply(Transition::Start).unwrap();
+        session.apply(Transition::Log).unwrap();
+    }
 
     #[test]
     fn test_start_session() {
         let mut session = Session::new();
-        assert_eq!(session.current_session, SessionType::Work);
-        session.start_session();
-        assert_eq!(session.current_session, SessionType::ShortBreak);
+        assert_eq!(session.current_session(), SessionType::Work);
+        assert_eq!(session.phase(), Phase::Idle);
+        finish(&mut session);
+        assert_eq!(session.current_session(), SessionType::ShortBreak);
         assert_eq!(session.work_sessions, 1);
-        session.start_session();
-        assert_eq!(session.current_session, SessionType::Work);
+        finish(&mut session);
+        assert_eq!(session.current_session(), SessionType::Work);
         assert_eq!(session.work_sessions, 1);
     }
 
@@ -21,47 +28,311 @@ mod session_tests {
     fn test_long_break_session() {
         let mut session = Session::new();
         for _ in 0..3 {
-            session.start_session();
-            session.start_session();
+            finish(&mut session);
+            finish(&mut session);
         }
-        assert_eq!(session.current_session, SessionType::LongBreak);
+        assert_eq!(session.current_session(), SessionType::Work);
+        finish(&mut session);
+        assert_eq!(session.current_session(), SessionType::LongBreak);
         assert_eq!(session.work_sessions, 4);
         assert_eq!(session.long_break_sessions, 0);
-        session.start_session();
-        assert_eq!(session.current_session, SessionType::Work);
+        finish(&mut session);
+        assert_eq!(session.current_session(), SessionType::Work);
         assert_eq!(session.work_sessions, 4);
         assert_eq!(session.long_break_sessions, 1);
     }
 
+    /// A session whose next break is a long one.
+    fn before_long_break() -> Session {
+        let mut session = Session::with_config(&Config {
+            sessions_long_break: Some(1),
+            ..Default::default()
+        });
+        finish(&mut session);
+        session
+    }
+
     #[test]
     fn test_get_session_duration() {
-        let session = Session::new();
+        let mut session = Session::new();
         assert_eq!(session.get_session_duration(), 25);
-        let mut session = Session {
-            current_session: SessionType::ShortBreak,
-            ..Default::default()
-        };
+        finish(&mut session);
         assert_eq!(session.get_session_duration(), 5);
-        let mut session = Session {
-            current_session: SessionType::LongBreak,
+        let session = before_long_break();
+        assert_eq!(session.get_session_duration(), 15);
+    }
+
+    #[test]
+    fn test_durations_come_from_config() {
+        let config = Config {
+            work_duration: Some(50),
+            short_break_duration: Some(10),
+            sessions_long_break: Some(2),
             ..Default::default()
         };
-        assert_eq!(session.get_session_duration(), 15);
+        let mut session = Session::with_config(&config);
+        assert_eq!(session.target_duration(), 50 * 60);
+        finish(&mut session);
+        assert_eq!(session.target_duration(), 10 * 60);
+        finish(&mut session);
+        finish(&mut session);
+        assert_eq!(session.current_session(), SessionType::LongBreak);
+    }
+
+    #[test]
+    fn test_transitions() {
+        let mut session = Session::new();
+        let event = session.apply(Transition::Start).unwrap();
+        assert_eq!(event.from, Phase::Idle);
+        assert_eq!(event.to, Phase::Running(SessionType::Work));
+        session.apply(Transition::Pause).unwrap();
+        assert_eq!(session.phase(), Phase::Paused(SessionType::Work));
+        session.apply(Transition::Resume).unwrap();
+        let event = session.apply(Transition::Complete).unwrap();
+        assert_eq!(event.to, Phase::AwaitingRating);
+        assert_eq!(event.current_session, SessionType::Work);
+        let event = session.apply(Transition::Log).unwrap();
+        assert_eq!(event.session_type, SessionType::Work);
+        assert_eq!(event.current_session, SessionType::ShortBreak);
+        assert_eq!(event.to, Phase::Idle);
+
+        // A finished break goes straight back to idle with work up next.
+        session.apply(Transition::Start).unwrap();
+        let event = session.apply(Transition::Complete).unwrap();
+        assert_eq!(event.to, Phase::Idle);
+        assert_eq!(session.current_session(), SessionType::Work);
+    }
+
+    #[test]
+    fn test_invalid_transitions_are_refused() {
+        let mut session = Session::new();
+        for transition in [
+            Transition::Pause,
+            Transition::Resume,
+            Transition::Complete,
+            Transition::Stop,
+            Transition::Log,
+            Transition::SkipBreak,
+        ] {
+            let error = session.apply(transition).unwrap_err();
+            assert_eq!(error.kind(), "state");
+        }
+        session.apply(Transition::Start).unwrap();
+        assert!(session.check(Transition::Start).is_err());
+        assert!(session.check(Transition::Resume).is_err());
+        assert_eq!(session.phase(), Phase::Running(SessionType::Work));
+        assert_eq!(session.work_sessions, 0);
+    }
+
+    #[test]
+    fn test_stop_does_not_count_work() {
+        let mut session = Session::new();
+        session.apply(Transition::Start).unwrap();
+        session.set_target_duration(600);
+        session.apply(Transition::Stop).unwrap();
+        assert_eq!(session.current_session(), SessionType::Work);
+        assert_eq!(session.work_sessions, 0);
+        assert_eq!(session.target_duration(), 600);
+
+        // Stopping a completed session or a break does move on.
+        session.apply(Transition::Start).unwrap();
+        session.apply(Transition::Complete).unwrap();
+        session.apply(Transition::Stop).unwrap();
+        assert_eq!(session.current_session(), SessionType::ShortBreak);
+        assert_eq!(session.work_sessions, 1);
+        session.apply(Transition::Start).unwrap();
+        session.apply(Transition::Stop).unwrap();
+        assert_eq!(session.current_session(), SessionType::Work);
     }
 
     #[test]
     fn test_skip_break() {
         let mut session = Session::new();
-        session.start_session();
-        assert_eq!(session.current_session, SessionType::ShortBreak);
-        session.skip_break();
-        assert_eq!(session.current_session, SessionType::Work);
-        let mut session = Session {
-            current_session: SessionType::LongBreak,
-            ..Default::default()
+        assert!(session.check(Transition::SkipBreak).is_err());
+        finish(&mut session);
+        assert_eq!(session.current_session(), SessionType::ShortBreak);
+        session.apply(Transition::SkipBreak).unwrap();
+        assert_eq!(session.current_session(), SessionType::Work);
+        assert_eq!(session.phase(), Phase::Idle);
+
+        let mut session = before_long_break();
+        session.apply(Transition::Start).unwrap();
+        session.apply(Transition::Pause).unwrap();
+        let event = session.apply(Transition::SkipBreak).unwrap();
+        assert_eq!(event.from, Phase::Paused(SessionType::LongBreak));
+        assert_eq!(event.to, Phase::Idle);
+        assert_eq!(session.current_session(), SessionType::Work);
+        assert_eq!(session.long_break_sessions, 0);
+    }
+
+    const SEQUENCES: &str = "
+sequence: three-then-thirty
+sequences:
+  52-17:
+    - { type: work, minutes: 52 }
+    - { type: shortBreak, minutes: 17, sound: chime }
+  three-then-thirty:
+    - { type: work, minutes: 25 }
+    - { type: work, minutes: 25 }
+    - { type: work, minutes: 25 }
+    - { type: longBreak, minutes: 30 }
+  writing:
+    - { type: outline, minutes: 15 }
+    - { type: draft, minutes: 45 }
+    - { type: walk, minutes: 10, break: true }
+";
+
+    fn sequences(selected: &str) -> Config {
+        Config {
+            sequence: Some(selected.to_string()),
+            ..ConfigCheck::new(SEQUENCES).into_result().unwrap()
+        }
+    }
+
+    #[test]
+    fn test_custom_sequence() {
+        let mut session = Session::with_config(&sequences("three-then-thirty"));
+        let mut cycle = Vec::new();
+        for _ in 0..5 {
+            cycle.push((session.current_session(), session.get_session_duration()));
+            finish(&mut session);
+        }
+        assert_eq!(
+            cycle,
+            vec![
+                (SessionType::Work, 25),
+                (SessionType::Work, 25),
+                (SessionType::Work, 25),
+                (SessionType::LongBreak, 30),
+                (SessionType::Work, 25),
+            ]
+        );
+        assert_eq!(session.work_sessions, 4);
+        assert_eq!(session.long_break_sessions, 1);
+    }
+
+    #[test]
+    fn test_named_segments() {
+        let mut session = Session::with_config(&sequences("writing"));
+        assert_eq!(
+            session.current_session(),
+            SessionType::Custom("outline".to_string())
+        );
+        assert!(!session.is_working());
+        session.apply(Transition::Start).unwrap();
+        assert!(session.is_working());
+        session.apply(Transition::Log).unwrap();
+        finish(&mut session);
+        assert_eq!(session.work_sessions, 2);
+        assert!(session.segment().is_break());
+        assert!(session.check(Transition::SkipBreak).is_ok());
+        session.apply(Transition::SkipBreak).unwrap();
+        assert_eq!(
+            session.current_session(),
+            SessionType::Custom("outline".to_string())
+        );
+        assert_eq!(session.work_sessions, 2);
+    }
+
+    #[test]
+    fn test_sequence_sounds() {
+        let config = Config {
+            work_sound: Some("bell".to_string()),
+            ..sequences("52-17")
         };
-        session.skip_break();
-        assert_eq!(session.current_session, SessionType::Work);
+        let sequence = Sequence::from_config(&config);
+        assert_eq!(sequence.segments[0].sound.as_deref(), Some("bell"));
+        assert_eq!(sequence.segments[1].sound.as_deref(), Some("chime"));
+        let names: Vec<_> = Sequence::all(&config).into_iter().map(|s| s.name).collect();
+        assert_eq!(
+            names,
+            [
+                "classic",
+                "flowtime",
+                "52-17",
+                "three-then-thirty",
+                "writing"
+            ]
+        );
     }
-}
 
+    #[test]
+    fn test_switch_sequence_when_idle() {
+        let mut session = Session::new();
+        finish(&mut session);
+        session.apply(Transition::Start).unwrap();
+        session.apply_config(&sequences("52-17"));
+        // The break under way finishes in the classic cycle.
+        assert_eq!(session.sequence().name, Sequence::CLASSIC);
+        assert_eq!(session.get_session_duration(), 5);
+        session.apply(Transition::Stop).unwrap();
+        assert_eq!(session.sequence().name, "52-17");
+        assert_eq!(session.position(), 0);
+        assert_eq!(session.get_session_duration(), 52);
+
+        // Edits to the sequence in use apply right away.
+        finish(&mut session);
+        let mut config = sequences("52-17");
+        config.sequences.as_mut().unwrap().get_mut("52-17").unwrap()[1].minutes = 20;
+        session.apply_config(&config);
+        assert_eq!(session.position(), 1);
+        assert_eq!(session.get_session_duration(), 20);
+    }
+
+    #[test]
+    fn test_flowtime() {
+        let mut session = Session::with_config(&Config {
+            sequence: Some(Sequence::FLOWTIME.to_string()),
+            ..Default::default()
+        });
+        assert!(session.is_open_ended());
+        session.apply(Transition::Start).unwrap();
+        assert!(session.check(Transition::Complete).is_ok());
+        // Stopping flowtime work counts it and moves on to its break.
+        let event = session
+            .apply_timed(Transition::Stop, Duration::from_secs(50 * 60))
+            .unwrap();
+        assert_eq!(event.current_session, SessionType::ShortBreak);
+        assert_eq!(session.work_sessions, 1);
+        assert!(!session.is_open_ended());
+        assert_eq!(session.target_duration(), 10 * 60);
+        finish(&mut session);
+        assert_eq!(session.current_session(), SessionType::Work);
+        assert!(session.is_open_ended());
+    }
+
+    #[test]
+    fn test_restored_position_past_the_end() {
+        let mut saved = serde_json::to_value(Session::new()).unwrap();
+        saved["position"] = 99.into();
+        let session: Session = serde_json::from_value(saved).unwrap();
+        assert_eq!(session.current_session(), SessionType::Work);
+    }
+
+    #[test]
+    fn test_restored_empty_sequence_is_refused() {
+        let mut saved = serde_json::to_value(Session::new()).unwrap();
+        saved["sequence"]["segments"] = serde_json::json!([]);
+        assert!(serde_json::from_value::<Session>(saved).is_err());
+    }
+
+    #[test]
+    fn test_session_type_names() {
+        assert_eq!("work".parse::<SessionType>().unwrap(), SessionType::Work);
+        assert_eq!(
+            "short_break".parse::<SessionType>().unwrap(),
+            SessionType::ShortBreak
+        );
+        assert_eq!(
+            "review".parse::<SessionType>().unwrap(),
+            SessionType::Custom("review".to_string())
+        );
+        assert!("".parse::<SessionType>().is_err());
+        let json = serde_json::to_string(&SessionType::Custom("review".to_string())).unwrap();
+        assert_eq!(json, "\"review\"");
+        assert_eq!(
+            serde_json::to_string(&SessionType::LongBreak).unwrap(),
+            "\"longBreak\""
+        );
+    }
+}
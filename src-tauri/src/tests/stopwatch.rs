@@ -1,49 +1,193 @@
 // tests/stopwatch.rs
-use std::thread::sleep;
-use std::time::Duration;
-
 #[cfg(test)]
 mod stopwatch_tests {
-    use crate::Stopwatch;
+    use crate::clock::{Clock, FakeClock};
+    use crate::config::SuspendPolicy;
+    use crate::{Stopwatch, TimerState};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    fn fake_stopwatch() -> (Stopwatch, FakeClock) {
+        let clock = FakeClock::default();
+        (Stopwatch::with_clock(Arc::new(clock.clone())), clock)
+    }
 
     #[test]
     fn test_start_and_stop() {
-        let mut stopwatch = Stopwatch::new();
+        let (mut stopwatch, clock) = fake_stopwatch();
         stopwatch.start();
-        sleep(Duration::from_millis(100));
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(stopwatch.elapsed(), 100);
+        assert_eq!(stopwatch.state(), TimerState::Running);
         stopwatch.stop();
-        assert!(stopwatch.elapsed() >= 100);
+        assert_eq!(stopwatch.elapsed(), 0);
+        assert_eq!(stopwatch.state(), TimerState::Stopped);
     }
 
     #[test]
     fn test_pause_and_resume() {
-        let mut stopwatch = Stopwatch::new();
+        let (mut stopwatch, clock) = fake_stopwatch();
         stopwatch.start();
-        sleep(Duration::from_millis(100));
+        clock.advance(Duration::from_millis(100));
         stopwatch.pause();
-        let elapsed_after_pause = stopwatch.elapsed();
-        sleep(Duration::from_millis(200));
+        assert_eq!(stopwatch.state(), TimerState::Paused);
+        clock.advance(Duration::from_millis(200));
+        assert_eq!(stopwatch.elapsed(), 100);
         stopwatch.resume();
-        sleep(Duration::from_millis(100));
-        stopwatch.stop();
-        assert_eq!(elapsed_after_pause, stopwatch.elapsed() - 300);
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(stopwatch.elapsed(), 200);
+        assert_eq!(stopwatch.paused_time(), 200);
     }
 
     #[test]
     fn test_format_time() {
-        let mut stopwatch = Stopwatch::new();
+        let (mut stopwatch, clock) = fake_stopwatch();
         stopwatch.start();
-        sleep(Duration::from_secs(65));
-        stopwatch.stop();
-        assert_eq!(stopwatch.format_time(), "01:05:00");
+        clock.advance(Duration::from_secs(65));
+        assert_eq!(stopwatch.format_time(), "00:01:05");
+        clock.advance(Duration::from_secs(3600));
+        assert_eq!(stopwatch.format_time(), "01:01:05");
     }
 
     #[test]
     fn test_reset() {
-        let mut stopwatch = Stopwatch::new();
+        let (mut stopwatch, clock) = fake_stopwatch();
         stopwatch.start();
-        sleep(Duration::from_millis(100));
+        clock.advance(Duration::from_millis(100));
         stopwatch.reset();
         assert_eq!(stopwatch.elapsed(), 0);
+        assert_eq!(stopwatch.started_at(), None);
+    }
+
+    #[test]
+    fn test_detect_suspend() {
+        let (mut stopwatch, clock) = fake_stopwatch();
+        assert_eq!(stopwatch.detect_suspend(), None);
+        stopwatch.start();
+        assert_eq!(stopwatch.detect_suspend(), None);
+        clock.advance(MINUTE);
+        assert_eq!(stopwatch.detect_suspend(), None);
+        // Small wall-clock corrections are not a suspend.
+        clock.suspend(Duration::from_secs(2));
+        assert_eq!(stopwatch.detect_suspend(), None);
+        clock.suspend(30 * MINUTE);
+        clock.advance(Duration::from_millis(250));
+        assert_eq!(stopwatch.detect_suspend(), Some(30 * MINUTE));
+        assert_eq!(stopwatch.detect_suspend(), None);
+    }
+
+    #[test]
+    fn test_stopwatch_adjustments() {
+        let (mut stopwatch, clock) = fake_stopwatch();
+        stopwatch.start();
+        stopwatch.add_elapsed(10 * MINUTE);
+        assert_eq!(stopwatch.elapsed(), (10 * MINUTE).as_millis());
+        clock.advance(MINUTE);
+        stopwatch.pause();
+        stopwatch.remove_elapsed(4 * MINUTE);
+        assert_eq!(stopwatch.elapsed(), (7 * MINUTE).as_millis());
+        stopwatch.remove_elapsed(60 * MINUTE);
+        assert_eq!(stopwatch.elapsed(), 0);
+
+        // More than the clock has been up for, which no `Instant` can go back.
+        let long = Duration::from_secs(100 * 365 * 24 * 60 * 60);
+        stopwatch.resume();
+        stopwatch.add_elapsed(long);
+        clock.advance(MINUTE);
+        assert_eq!(stopwatch.elapsed(), (long + MINUTE).as_millis());
+    }
+
+    #[test]
+    fn test_apply_suspend() {
+        let gap = 10 * MINUTE;
+        let (mut stopwatch, clock) = fake_stopwatch();
+        stopwatch.start();
+        clock.advance(MINUTE);
+        stopwatch.apply_suspend(gap, SuspendPolicy::Count);
+        assert_eq!(stopwatch.elapsed(), (11 * MINUTE).as_millis());
+        assert!(stopwatch.is_running());
+
+        let (mut stopwatch, clock) = fake_stopwatch();
+        stopwatch.start();
+        clock.advance(MINUTE);
+        clock.suspend(gap);
+        stopwatch.apply_suspend(gap, SuspendPolicy::Exclude);
+        assert_eq!(stopwatch.elapsed(), MINUTE.as_millis());
+        assert!(stopwatch.is_running());
+        stopwatch.apply_suspend(gap, SuspendPolicy::Pause);
+        assert!(!stopwatch.is_running());
+        clock.advance(MINUTE);
+        assert_eq!(stopwatch.elapsed(), MINUTE.as_millis());
+        assert_eq!(stopwatch.suspended(), 2 * gap);
+        stopwatch.stop();
+        assert_eq!(stopwatch.suspended(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_laps() {
+        let (mut stopwatch, clock) = fake_stopwatch();
+        assert_eq!(stopwatch.lap(None), None);
+        stopwatch.start();
+        clock.advance(10 * MINUTE);
+        let first = stopwatch.lap(Some("reviewing PR".to_string())).unwrap();
+        assert_eq!(first.label.as_deref(), Some("reviewing PR"));
+        assert_eq!(first.split_ms, (10 * MINUTE).as_millis() as u64);
+        assert_eq!(first.taken_at, clock.wall());
+
+        clock.advance(5 * MINUTE);
+        stopwatch.pause();
+        clock.advance(MINUTE);
+        let second = stopwatch.lap(Some(" ".to_string())).unwrap();
+        assert_eq!(second.label, None);
+        assert_eq!(second.elapsed_ms, (15 * MINUTE).as_millis() as u64);
+        assert_eq!(second.split_ms, (5 * MINUTE).as_millis() as u64);
+        assert_eq!(stopwatch.laps(), &[first, second]);
+
+        let restored = Stopwatch::restore_with_clock(&stopwatch.snapshot(), Arc::new(clock));
+        assert_eq!(restored.laps(), stopwatch.laps());
+        stopwatch.stop();
+        assert!(stopwatch.laps().is_empty());
+    }
+
+    #[test]
+    fn test_restore_counts_time_since_snapshot() {
+        let (mut stopwatch, clock) = fake_stopwatch();
+        stopwatch.start();
+        clock.advance(MINUTE);
+        let snapshot = stopwatch.snapshot();
+        clock.advance(2 * MINUTE);
+        let restored = Stopwatch::restore_with_clock(&snapshot, Arc::new(clock.clone()));
+        assert_eq!(restored.elapsed(), (3 * MINUTE).as_millis());
+        assert!(restored.is_running());
+
+        stopwatch.pause();
+        let snapshot = stopwatch.snapshot();
+        clock.advance(MINUTE);
+        let restored = Stopwatch::restore_with_clock(&snapshot, Arc::new(clock.clone()));
+        assert_eq!(restored.elapsed(), (3 * MINUTE).as_millis());
+        assert_eq!(restored.state(), TimerState::Paused);
+        assert_eq!(restored.clock().wall(), clock.wall());
+    }
+
+    #[test]
+    fn test_restore_longer_than_uptime() {
+        // Further back than an `Instant` can go on a freshly booted machine.
+        let years = 100 * 365 * 24 * 60 * MINUTE;
+        let clock = FakeClock::default();
+        let snapshot = serde_json::from_value(serde_json::json!({
+            "elapsed_ms": years.as_millis() as u64,
+            "running": true,
+            "started_at": clock.wall(),
+            "taken_at": clock.wall(),
+        }))
+        .unwrap();
+        let mut restored = Stopwatch::restore_with_clock(&snapshot, Arc::new(clock.clone()));
+        assert_eq!(restored.state(), TimerState::Running);
+        clock.advance(MINUTE);
+        assert_eq!(restored.elapsed(), (years + MINUTE).as_millis());
+        restored.remove_elapsed(years);
+        assert_eq!(restored.elapsed(), MINUTE.as_millis());
     }
 }
@@ -0,0 +1,57 @@
+//! Reloads `config.yaml` when it changes on disk.
+//!
+//! The whole config directory is watched rather than the file itself, since
+//! most editors save by writing a new file and renaming it over the old one.
+//! Hook scripts need no reload: they are looked up again on every run.
+use crate::config::{self, Config, CONFIG_DIRS};
+use crate::error::Result;
+use crate::{apply_config, notify_on_error};
+use notify::{Event, RecursiveMode, Watcher};
+use std::path::Path;
+use std::sync::mpsc::channel;
+use std::thread;
+use std::time::Duration;
+
+/// Editors often touch the file several times per save; wait for it to
+/// settle before reading.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+pub fn spawn() -> Result<()> {
+    let dir = CONFIG_DIRS[0].clone();
+    std::fs::create_dir_all(&dir)?;
+
+    let (sender, receiver) = channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+
+    thread::spawn(move || {
+        // Keep the watcher alive for as long as the thread runs.
+        let _watcher = watcher;
+        let config_file = Config::file();
+        while let Ok(event) = receiver.recv() {
+            if !touches(&event, &config_file) {
+                continue;
+            }
+            while receiver.recv_timeout(DEBOUNCE).is_ok() {}
+
+            let previous = config::current();
+            let config = config::reload();
+            if config != previous {
+                if let Err(e) = apply_config(&config) {
+                    notify_on_error!(e);
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+fn touches(event: &notify::Result<Event>, file: &Path) -> bool {
+    match event {
+        Ok(event) => event.paths.iter().any(|path| path == file),
+        Err(e) => {
+            eprintln!("mytt: config watcher: {}", e);
+            false
+        }
+    }
+}
@@ -8,7 +8,10 @@ export const LABELS = {
     FLOW: 'In Flow',
     FOCUSED: 'Focused',
     OK: 'OK',
-    DISTRACTED: 'Distracted'
+    DISTRACTED: 'Distracted',
+    KEEP_IDLE: 'Keep',
+    DISCARD_IDLE: 'Discard',
+    REASSIGN_IDLE: 'Break'
 } as const;
 
 export const SESSION_TYPES = {
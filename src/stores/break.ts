@@ -38,15 +38,26 @@ export class BreakStore {
         this.update(s => ({ ...s, ...partial }));
     }
 
-    skip(): void {
-        // Update break state
+    /**
+     * Skips the break. Resolves to false when an `on_skip_break` hook vetoed
+     * it, in which case the break state is left untouched.
+     */
+    async skip(): Promise<boolean> {
+        const previous = this.state;
         this.update(s => ({
             ...s,
             type: BREAK_TYPE.OPTIONAL,
             skipped: true
         }));
 
-        invoke('on_skip_break');
+        try {
+            await invoke('on_skip_break');
+            return true;
+        } catch (e) {
+            console.error('Break skip refused:', e);
+            this.store.set(previous);
+            return false;
+        }
     }
 
     start(flowStatus: FlowStatus, flowStreak: number): void {
@@ -1,5 +1,6 @@
 import { writable, get, Writable } from 'svelte/store';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import type { AppConfig, ConfigState } from '../types/config';
 import { timerStore } from './timer';
 import { uiStore } from './ui';
@@ -39,41 +40,20 @@ export class ConfigStore {
 
         // Load config immediately
         this.initializeConfig();
+
+        // Pick up edits made to config.yaml while the app is running
+        const unlistenChanged = listen<any>('config_changed', (event) => this.applyConfig(event.payload));
+        if (typeof window !== 'undefined') {
+            window.addEventListener('beforeunload', () => {
+                unlistenChanged.then(unlisten => unlisten());
+            });
+        }
     }
 
     private async initializeConfig() {
         try {
             const config: any = await invoke('fetch_config').then(JSON.parse as any) || {};
-            const newConfig: AppConfig = {
-                timer: {
-                    workDuration: config.work_duration * 60 || DURATIONS.DEFAULT_START,
-                    shortBreakDuration: config.short_break_duration * 60 || DURATIONS.BREAKS.OK,
-                    longBreakDuration: config.long_break_duration * 60 || DURATIONS.BREAKS.FLOW,
-                    sessionsBeforeLongBreak: config.sessions_before_long_break,
-                    minimumDuration: config.minimum_duration * 60 || DURATIONS.MINIMUM,
-                    maximumDuration: config.maximum_duration * 60 || DURATIONS.MAXIMUM
-                },
-                behavior: {
-                    annoyingLevel: config.annoying_level as typeof ANNOYING_LEVELS[keyof typeof ANNOYING_LEVELS] || ANNOYING_LEVELS.HIGH,
-                    theme: config.theme as 'light' | 'dark' || 'dark'
-                },
-                system: {
-                    frontmostApp: config.frontmost_app || ''
-                }
-            };
-
-            this.set({
-                ...newConfig,
-                isLoaded: true,
-                isError: false
-            });
-
-            // Update related stores
-            this.updateRelatedStores(newConfig);
-
-            console.log(newConfig);
-
-            return newConfig;
+            return this.applyConfig(config);
         } catch (error) {
             console.error('Error loading config:', error);
             this.set({
@@ -84,6 +64,37 @@ export class ConfigStore {
         }
     }
 
+    private applyConfig(config: any): AppConfig {
+        const newConfig: AppConfig = {
+            timer: {
+                workDuration: config.work_duration * 60 || DURATIONS.DEFAULT_START,
+                shortBreakDuration: config.short_break_duration * 60 || DURATIONS.BREAKS.OK,
+                longBreakDuration: config.long_break_duration * 60 || DURATIONS.BREAKS.FLOW,
+                sessionsBeforeLongBreak: config.sessions_long_break ?? config.sessions_before_long_break,
+                minimumDuration: config.minimum_duration * 60 || DURATIONS.MINIMUM,
+                maximumDuration: config.maximum_duration * 60 || DURATIONS.MAXIMUM
+            },
+            behavior: {
+                annoyingLevel: config.annoying_level as typeof ANNOYING_LEVELS[keyof typeof ANNOYING_LEVELS] || ANNOYING_LEVELS.HIGH,
+                theme: config.theme as 'light' | 'dark' || 'dark'
+            },
+            system: {
+                frontmostApp: config.frontmost_app || ''
+            }
+        };
+
+        this.set({
+            ...newConfig,
+            isLoaded: true,
+            isError: false
+        });
+
+        // Update related stores
+        this.updateRelatedStores(newConfig);
+
+        return newConfig;
+    }
+
     get state(): ConfigState {
         return get(this.store);
     }
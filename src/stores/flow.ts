@@ -1,6 +1,8 @@
 import { writable, Writable, get } from 'svelte/store';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { FLOW_STATUS, BREAK_TYPE } from '../constants';
-import type { FlowState, FlowStatus, SessionType, BreakType } from '../types/index';
+import type { FlowState, FlowStatus, FocusStats, SessionType, BreakType } from '../types/index';
 import { timerStore } from './timer';
 import { sessionStore } from './session';
 import { breakStore } from './break';
@@ -33,6 +35,19 @@ export class FlowStore {
         this.store.subscribe((state: FlowState) => {
             localStorage.setItem('flowStore', JSON.stringify(state));
         });
+
+        // The Rust core keeps the streak and daily goal, including for
+        // ratings given with `mytt rate`
+        invoke<FocusStats>('get_focus_stats').then(stats => this.applyStats(stats)).catch(console.error);
+        listen<FocusStats>('focus_stats', (event) => this.applyStats(event.payload));
+    }
+
+    applyStats(stats: FocusStats): void {
+        this.set({
+            streak: stats.streak,
+            dailyFlowSessions: stats.flow_today,
+            dailyFlowGoal: stats.daily_goal,
+        });
     }
 
     get state(): FlowState {
@@ -99,7 +114,8 @@ export class FlowStore {
         return {
             current: dailyFlowSessions,
             goal: dailyFlowGoal,
-            percentage: (dailyFlowSessions / dailyFlowGoal) * 100
+            // A goal of 0 means there is none to make progress on
+            percentage: dailyFlowGoal ? (dailyFlowSessions / dailyFlowGoal) * 100 : 0
         };
     }
 
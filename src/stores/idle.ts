@@ -0,0 +1,55 @@
+import { writable, get, Writable } from 'svelte/store';
+import { listen } from '@tauri-apps/api/event';
+import { invoke } from '@tauri-apps/api/core';
+import { TIMER_STATUS } from '../constants';
+import type { IdleAction, IdlePeriod } from '../types/index';
+import { timerStore } from './timer';
+
+export interface IdleState {
+    period: IdlePeriod | null;
+}
+
+/**
+ * Mirrors the time away the Rust core noticed, so the user can say what it
+ * was once they are back.
+ */
+export class IdleStore {
+    private store: Writable<IdleState>;
+
+    constructor() {
+        this.store = writable<IdleState>({ period: null });
+
+        listen<IdlePeriod>('idle_started', (event) => {
+            this.store.set({ period: event.payload });
+            timerStore.set({ status: TIMER_STATUS.PAUSED });
+        });
+        listen<IdlePeriod>('idle_ended', (event) => this.store.set({ period: event.payload }));
+        listen('idle_resolved', () => {
+            this.store.set({ period: null });
+            if (timerStore.state.status === TIMER_STATUS.PAUSED) {
+                timerStore.set({ status: TIMER_STATUS.RUNNING });
+            }
+        });
+    }
+
+    get state(): IdleState {
+        return get(this.store);
+    }
+
+    subscribe = (run: (value: IdleState) => void): (() => void) => {
+        return this.store.subscribe(run);
+    }
+
+    /**
+     * Whether the user is back and has not yet said what the time away was.
+     */
+    get needsAnswer(): boolean {
+        return this.state.period?.ended_at != null;
+    }
+
+    resolve(action: IdleAction): Promise<void> {
+        return invoke('resolve_idle', { action });
+    }
+}
+
+export const idleStore = new IdleStore();
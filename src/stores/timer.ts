@@ -16,13 +16,16 @@ import { writable, get, Writable } from 'svelte/store';
 import { TIMER_STATUS, SESSION_TYPES, ANNOYING_LEVELS, FLOW_STATUS as FLOW_STATUS, DURATIONS, BREAK_TYPE } from '../constants';
 import { flowStore } from './flow';
 import { sessionStore } from './session';
-import { notify } from '../utils/notification';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import * as utils from '../utils/time';
 
 import type {
     BreakType,
+    CoreStatus,
+    DurationSuggestion,
     FlowStatus,
+    FocusStats,
     TimerStoreState,
 } from '../types/index';
 
@@ -83,9 +86,8 @@ interface ITimerStore {
  */
 class TimerStore implements ITimerStore {
     store: Writable<TimerStoreState>;
-    private animationFrameId: number | undefined;
-    private lastUpdateTime: number | undefined;
-    private intervalId: number | undefined;
+    // A finished work session the core holds until it is rated
+    private awaitingRating = false;
 
     // Getters for commonly accessed state
     get state(): TimerStoreState {
@@ -107,18 +109,25 @@ class TimerStore implements ITimerStore {
         }
 
         this.store = writable<TimerStoreState>(DEFAULT_STATE);
-        this.animationFrameId = undefined;
-        this.lastUpdateTime = undefined;
 
         // Setup persistent storage
         const unsubscribe = this.store.subscribe((state: TimerStoreState) => {
             localStorage.setItem('timerStore', JSON.stringify(state));
         });
 
+        // The Rust core owns the countdown; mirror it here
+        const unlistenTick = listen<CoreStatus>('tick', (event) => this.onTick(event.payload));
+        const unlistenCompleted = listen<CoreStatus>('session_completed', () => this.onSessionCompleted());
+        // With `suspend_policy: pause` the core pauses a session the computer slept through
+        const unlistenSuspend = listen('suspend_paused', () => this.set({ status: TIMER_STATUS.PAUSED }));
+
         // Ensure cleanup when store is destroyed
         if (typeof window !== 'undefined') {
             window.addEventListener('beforeunload', () => {
                 unsubscribe();
+                unlistenTick.then(unlisten => unlisten());
+                unlistenCompleted.then(unlisten => unlisten());
+                unlistenSuspend.then(unlisten => unlisten());
             });
         }
     }
@@ -140,117 +149,87 @@ class TimerStore implements ITimerStore {
         this.store.set(DEFAULT_STATE);
     }
 
-    start(): void {
-        this.lastUpdateTime = Date.now();
-
-        // Use setInterval for consistent timer updates
-        this.intervalId = window.setInterval(() => {
-            const currentTime = Date.now();
-            const deltaTime = currentTime - (this.lastUpdateTime || currentTime);
-            const secondsToDecrease = Math.floor(deltaTime / 1000);
-
-            if (this.state.status === TIMER_STATUS.STOPPED) {
-                this.stop();
-                return;
-            }
-
-            if (this.state.status === TIMER_STATUS.PAUSED) {
-                return;
-            }
-
-            // Update progress bar
-            const remaining = Math.max(0, this.state.time.remaining);
-            const total = this.state.time.total;
-            const percentage = Math.max(0, Math.min(100, (remaining / total) * 100));
-
-            this.update(s => ({
-                ...s,
-                progress: {
-                    ...s.progress,
-                    percentage
-                }
-            }));
-
-            if (secondsToDecrease <= 0) {
-                return;
-            }
+    /**
+     * Mirrors the remaining time reported by the Rust core while running.
+     * Flowtime work has none and shows the time spent so far instead.
+     */
+    private onTick(status: CoreStatus): void {
+        if (this.state.status !== TIMER_STATUS.RUNNING || status.state !== TIMER_STATUS.RUNNING) {
+            return;
+        }
 
-            const newRemainingTime = Math.max(0, this.state.time.remaining - secondsToDecrease);
+        if (status.open_ended) {
+            const elapsed = Math.floor(status.elapsed_ms / 1000);
             this.update(s => ({
                 ...s,
                 time: {
                     ...s.time,
-                    remaining: newRemainingTime,
-                    display: utils.formatTime(newRemainingTime)
+                    display: utils.formatTime(elapsed)
                 }
             }));
+            return;
+        }
 
-            this.lastUpdateTime = currentTime - (deltaTime % 1000);
-
-            if (newRemainingTime !== 0) {
-                return;
-            }
-
-            // if (sessionStore.state.type === SESSION_TYPES.WORK) {
-            switch (true) {
-                case sessionStore.state.type === SESSION_TYPES.WORK:
-                    notify({
-                        title: "Work Session Complete!",
-                        body: "Time for a break! Keep up the good work!",
-                    });
-                    this.set({
-                        status: TIMER_STATUS.STOPPED,
-                        time: {
-                            ...this.state.time,
-                            remaining: this.state.preferences.workDuration,
-                            display: utils.formatTime(this.state.preferences.workDuration)
-                        },
-                    });
-                    break;
-                default:
-                    notify({
-                        title: "Break Time Over",
-                        body: "Ready to get back to work?",
-                    });
-                    sessionStore.set({
-                        type: SESSION_TYPES.WORK,
-                        completed: sessionStore.state.completed + 1,
-                        lastSessionDuration: undefined,
-                        suggestedNextDuration: undefined,
-                        sessionGrowth: undefined
-                    });
-                    this.set({
-                        time: {
-                            total: this.state.preferences.workDuration,
-                            remaining: this.state.preferences.workDuration,
-                            display: utils.formatTime(this.state.preferences.workDuration)
-                        },
-                        status: TIMER_STATUS.STOPPED,
-                    });
-            }
-            this.stop();
-
-        }, 100);
+        const remaining = Math.ceil(status.remaining_ms / 1000);
+        const total = this.state.time.total;
+        const percentage = Math.max(0, Math.min(100, (remaining / total) * 100));
 
-        // Use requestAnimationFrame for smooth UI updates
-        const animate = () => {
-            if (this.state.status === TIMER_STATUS.RUNNING) {
-                this.animationFrameId = requestAnimationFrame(animate);
+        this.update(s => ({
+            ...s,
+            time: {
+                ...s.time,
+                remaining,
+                display: utils.formatTime(remaining)
+            },
+            progress: {
+                ...s.progress,
+                percentage
             }
-        };
-        this.animationFrameId = requestAnimationFrame(animate);
+        }));
+    }
 
-        if (this.state.time.remaining <= 0) {
-            this.update(s => ({
-                ...s,
-                time: {
-                    ...s.time,
-                    remaining: this.state.preferences.workDuration,
-                    display: utils.formatTime(s.preferences.workDuration)
-                },
-            }));
+    /**
+     * Called when the Rust core finished the session. The core has already
+     * sent the notification and run the `on_complete` hook.
+     */
+    private onSessionCompleted(): void {
+        switch (true) {
+            case sessionStore.state.type === SESSION_TYPES.WORK:
+                this.set({
+                    status: TIMER_STATUS.STOPPED,
+                    time: {
+                        ...this.state.time,
+                        remaining: this.state.preferences.workDuration,
+                        display: utils.formatTime(this.state.preferences.workDuration)
+                    },
+                });
+                break;
+            default:
+                sessionStore.set({
+                    type: SESSION_TYPES.WORK,
+                    completed: sessionStore.state.completed + 1,
+                    lastSessionDuration: undefined,
+                    suggestedNextDuration: undefined,
+                    sessionGrowth: undefined
+                });
+                this.set({
+                    time: {
+                        total: this.state.preferences.workDuration,
+                        remaining: this.state.preferences.workDuration,
+                        display: utils.formatTime(this.state.preferences.workDuration)
+                    },
+                    status: TIMER_STATUS.STOPPED,
+                });
+                // The core has already put a finished break behind it.
+                this.halt();
+                return;
         }
+        // Stopping now would put the session behind before it is rated.
+        this.awaitingRating = true;
+        this.halt();
+    }
 
+    start(): void {
         flowStore.set({
             ...flowStore.state,
             prompt: {
@@ -264,7 +243,29 @@ class TimerStore implements ITimerStore {
             status: TIMER_STATUS.RUNNING,
         }));
 
-        invoke('on_start');
+        // A hook may already have chosen the length of this session, in which
+        // case the core keeps it and reports it back.
+        const seconds = this.state.time.remaining > 0
+            ? this.state.time.remaining
+            : this.state.preferences.workDuration;
+        // A finished session that was never rated is put behind without a
+        // rating; it may already have been logged from the Log button.
+        const unrated = this.awaitingRating
+            ? invoke('on_stop').catch(() => undefined)
+            : Promise.resolve();
+        this.awaitingRating = false;
+        unrated.then(() => invoke<CoreStatus>('on_start', { seconds })).then(status => {
+            const total = Math.ceil((status.elapsed_ms + status.remaining_ms) / 1000);
+            const remaining = Math.ceil(status.remaining_ms / 1000);
+            this.update(s => ({
+                ...s,
+                time: {
+                    total,
+                    remaining,
+                    display: utils.formatTime(remaining)
+                },
+            }));
+        });
     }
 
     pause(): void {
@@ -273,7 +274,6 @@ class TimerStore implements ITimerStore {
             return;
         }
 
-        this.lastUpdateTime = Date.now(); // Save the exact pause time
         this.update((s: TimerStoreState) => ({
             ...s,
             status: TIMER_STATUS.PAUSED
@@ -286,7 +286,6 @@ class TimerStore implements ITimerStore {
         if (this.state.status !== TIMER_STATUS.PAUSED) {
             return;
         }
-        this.lastUpdateTime = Date.now();
         this.update((s: TimerStoreState) => ({
             ...s,
             status: TIMER_STATUS.RUNNING
@@ -296,23 +295,22 @@ class TimerStore implements ITimerStore {
     }
 
     stop(): void {
+        this.halt();
+        invoke('on_stop');
+    }
+
+    /**
+     * Marks the timer stopped without telling the core.
+     */
+    private halt(): void {
         this.update((s: TimerStoreState) => ({
             ...s,
             status: TIMER_STATUS.STOPPED,
             time: {
                 ...s.time,
-                remaining: sessionStore.state.type === 'work' ? this.state.preferences.workDuration : this.state.preferences.workDuration,
+                remaining: this.state.preferences.workDuration,
             },
         }));
-        if (this.animationFrameId) {
-            cancelAnimationFrame(this.animationFrameId);
-            this.animationFrameId = undefined;
-        }
-        if (this.intervalId) {
-            clearInterval(this.intervalId);
-            this.intervalId = undefined;
-        }
-        this.lastUpdateTime = undefined;
 
         flowStore.set({
             ...flowStore.state,
@@ -321,8 +319,6 @@ class TimerStore implements ITimerStore {
                 isActive: true
             }
         });
-
-        invoke('on_stop');
     }
 
     reset(): void {
@@ -330,79 +326,38 @@ class TimerStore implements ITimerStore {
         this.state = DEFAULT_STATE;
     }
 
-    nextDuration(currentDuration: number, flowStatus: FlowStatus, flowStreak: number, customDuration?: number): number {
-        // If a custom duration was set through the progress bar, use that
-        if (customDuration !== undefined) {
-            const boundedDuration = Math.min(
-                Math.max(customDuration, DURATIONS.MINIMUM),
-                DURATIONS.MAXIMUM
-            );
-
-            this.update(s => ({
-                ...s,
-                preferences: {
-                    ...s.preferences,
-                    workDuration: boundedDuration
-                },
-                time: {
-                    total: boundedDuration,
-                    remaining: boundedDuration,
-                    display: utils.formatTime(boundedDuration)
-                }
-            }));
-
-            return boundedDuration;
-        }
-
-        // Otherwise use the flow-based duration adjustment logic
-        let newDuration = currentDuration;
-        let reason = '';
+    /**
+     * Records `rating` for the work session that just finished in the Rust
+     * core, which logs the session with it if it was still waiting for one.
+     */
+    async rate(rating: FlowStatus, note?: string): Promise<FocusStats> {
+        this.awaitingRating = false;
+        return invoke<FocusStats>('rate_session', { rating, note });
+    }
 
-        switch (flowStatus) {
-            case FLOW_STATUS.FLOW:
-                reason = 'Extending duration due to flow state';
-                newDuration = Math.min(
-                    currentDuration + DURATIONS.INCREMENT.FLOW,
-                    DURATIONS.MAXIMUM
-                );
-                break;
-            case FLOW_STATUS.FOCUSED:
-                reason = 'Increasing duration due to high focus';
-                newDuration = Math.min(
-                    currentDuration + DURATIONS.INCREMENT.FOCUSED,
-                    DURATIONS.MAXIMUM
-                );
-                break;
-            case FLOW_STATUS.OK:
-                reason = 'Maintaining current duration';
-                newDuration = Math.min(
-                    currentDuration + DURATIONS.INCREMENT.OK,
-                    DURATIONS.MAXIMUM
-                );
-                break;
-            case FLOW_STATUS.DISTRACTED:
-                reason = 'Reducing duration due to distraction';
-                newDuration = Math.max(
-                    DURATIONS.MINIMUM,
-                    currentDuration + DURATIONS.INCREMENT.DISTRACTED
-                );
-                break;
-        }
+    /**
+     * Asks the Rust core how long the next work session and break should be
+     * after the ratings so far, and makes the work length the one to start
+     * with.
+     */
+    async nextDuration(): Promise<DurationSuggestion> {
+        const suggestion = await invoke<DurationSuggestion>('suggest_durations');
+        const workDuration = suggestion.work_seconds;
 
         this.update(s => ({
             ...s,
             preferences: {
                 ...s.preferences,
-                workDuration: newDuration
+                workDuration
             },
             time: {
-                total: newDuration,
-                remaining: newDuration,
-                display: utils.formatTime(newDuration)
+                total: workDuration,
+                remaining: workDuration,
+                display: utils.formatTime(workDuration)
             }
         }));
 
-        return newDuration;
+        return suggestion;
     }
 
     shouldTakeBreak(flowStatus: FlowStatus, flowStreak: number): BreakType {
@@ -18,6 +18,136 @@ export interface ProgressState {
 // Timer Related Types
 export type TimerStatus = 'running' | 'paused' | 'stopped';
 
+/**
+ * Timer snapshot emitted by the Rust core with every `tick` and
+ * `session_completed` event.
+ */
+export interface CoreStatus {
+    session_type: SessionType;
+    phase: Phase;
+    state: TimerStatus;
+    elapsed_ms: number;
+    remaining_ms: number;
+    /** Flowtime work, which counts up until it is stopped. */
+    open_ended: boolean;
+}
+
+/**
+ * Where the core's session is. Breaks run and pause like work sessions.
+ */
+export type Phase =
+    | { phase: 'idle' }
+    | { phase: 'running'; session_type: SessionType }
+    | { phase: 'paused'; session_type: SessionType }
+    | { phase: 'awaiting_rating' };
+
+export type Transition = 'start' | 'pause' | 'resume' | 'complete' | 'stop' | 'log' | 'skip_break';
+
+/**
+ * Sent with the `session_transition` event whenever the core's session moves
+ * from one phase to another.
+ */
+export interface SessionEvent {
+    transition: Transition;
+    from: Phase;
+    to: Phase;
+    session_type: SessionType;
+    current_session: SessionType;
+}
+
+/**
+ * What to do with the time away from a session paused as idle.
+ */
+export type IdleAction = 'keep' | 'discard' | 'reassign';
+
+/**
+ * Time away from a work session, sent with the `idle_started` and
+ * `idle_ended` events.
+ */
+export interface IdlePeriod {
+    started_at: string;
+    ended_at: string | null;
+}
+
+/**
+ * A split inside a session, returned by `lap_timer` and sent with the `lap`
+ * event.
+ */
+export interface Lap {
+    label: string | null;
+    elapsed_ms: number;
+    split_ms: number;
+    taken_at: string;
+}
+
+/**
+ * Next work and break lengths in seconds, as returned by `suggest_durations`.
+ */
+export interface DurationSuggestion {
+    work_seconds: number;
+    break_seconds: number;
+    reason: string;
+}
+
+/**
+ * Flow streak and daily goal, as returned by `rate_session` and sent with
+ * the `focus_stats` event.
+ */
+export interface FocusStats {
+    streak: number;
+    flow_today: number;
+    /** 0 when there is no daily goal. */
+    daily_goal: number;
+}
+
+/**
+ * One step of a sequence.
+ */
+export interface Segment {
+    type: SessionType;
+    minutes: number;
+    break?: boolean;
+    sound?: string;
+}
+
+export interface Sequence {
+    name: string;
+    segments: Segment[];
+    flowtime?: Flowtime;
+}
+
+/**
+ * How the break after flowtime work is sized.
+ */
+export interface Flowtime {
+    ratio: number | null;
+    brackets: { up_to: number | null; minutes: number }[] | null;
+}
+
+/**
+ * The sequences on offer, as returned by `list_sequences`.
+ */
+export interface SequenceList {
+    active: string;
+    position: number;
+    sequences: Sequence[];
+}
+
+/**
+ * One hook script run, as returned by `list_hook_runs`.
+ */
+export interface HookRun {
+    event: string;
+    script: string;
+    started_at: string;
+    duration_ms: number;
+    exit_code: number | null;
+    success: boolean;
+    stdout: string;
+    stderr: string;
+    error: string | null;
+}
+
 export interface TimerStoreState {
     time: TimeState;
     progress: ProgressState;
@@ -64,7 +194,8 @@ export interface FocusState {
 }
 
 // Session and Break Related Types
-export type SessionType = 'work' | 'shortBreak' | 'longBreak';
+// Segments of a custom sequence can have a type of their own, e.g. 'review'.
+export type SessionType = 'work' | 'shortBreak' | 'longBreak' | (string & {});
 export type SessionGrowth = 'decrease' | 'maintain' | 'increase';
 export type BreakType = 'optional' | 'suggested' | 'required';
 